@@ -1,67 +1,96 @@
 use super::model::*;
-use crate::opt::Sport;
+use crate::{
+    cache::{self, Cache},
+    league::LeagueProvider,
+    net::HttpContext,
+};
+use chrono::{DateTime, Duration, Local, NaiveDate, Utc};
 use failure::Error;
-use stats_api::{MlbClient, NhlClient};
+use std::{sync::Arc, time::Duration as StdDuration};
 
 pub struct Client {
-    mlb: MlbClient,
-    nhl: NhlClient,
-    sport: Sport,
+    league: Arc<dyn LeagueProvider>,
+    http: Arc<HttpContext>,
+    cache: Cache,
 }
 
 impl Client {
-    pub fn new(sport: Sport) -> Self {
-        let mlb = MlbClient::default();
-        let nhl = NhlClient::default();
+    pub fn new(league: Arc<dyn LeagueProvider>, http: Arc<HttpContext>, cache: Cache) -> Self {
+        Client {
+            league,
+            http,
+            cache,
+        }
+    }
 
-        Client { mlb, nhl, sport }
+    /// League requests are made to
+    pub fn league(&self) -> &dyn LeagueProvider {
+        self.league.as_ref()
     }
 
-    pub async fn get_schedule_for(&self, date: chrono::NaiveDate) -> Result<Schedule, Error> {
-        let serialized = match &self.sport {
-            Sport::Mlb => {
-                let schedule = self.mlb.get_schedule_for(date).await?;
-                serde_json::to_vec(&schedule)?
-            }
-            Sport::Nhl => {
-                let schedule = self.nhl.get_schedule_for(date).await?;
-                serde_json::to_vec(&schedule)?
-            }
-        };
+    /// League requests are made to, shared with the streams of its games
+    pub fn shared_league(&self) -> Arc<dyn LeagueProvider> {
+        self.league.clone()
+    }
 
-        let schedule = serde_json::from_slice(&serialized)?;
-        Ok(schedule)
+    /// HTTP context requests are made with, shared with stream resolution
+    pub fn http(&self) -> Arc<HttpContext> {
+        self.http.clone()
     }
 
-    pub async fn get_game_content(&self, game_pk: u64) -> Result<GameContentResponse, Error> {
-        let serialized = match &self.sport {
-            Sport::Mlb => {
-                let game_content = self.mlb.get_game_content(game_pk).await?;
-                serde_json::to_vec(&game_content)?
-            }
-            Sport::Nhl => {
-                let game_content = self.nhl.get_game_content(game_pk).await?;
-                serde_json::to_vec(&game_content)?
-            }
+    pub async fn get_schedule_for(&self, date: NaiveDate) -> Result<Schedule, Error> {
+        let ttl = if date < Local::today().naive_local() {
+            cache::PAST_SCHEDULE_TTL
+        } else {
+            cache::SCHEDULE_TTL
         };
 
-        let game_content = serde_json::from_slice(&serialized)?;
-        Ok(game_content)
+        self.schedule_for(date, ttl).await
     }
 
-    pub async fn get_teams(&self) -> Result<Vec<Team>, Error> {
-        let serialized = match &self.sport {
-            Sport::Mlb => {
-                let teams = self.mlb.get_all_teams().await?;
-                serde_json::to_vec(&teams)?
-            }
-            Sport::Nhl => {
-                let teams = self.nhl.get_teams().await?;
-                serde_json::to_vec(&teams)?
-            }
+    /// Get the schedule from the API, for when the latest status is needed. It's still
+    /// cached, so it's read from the cache when offline
+    pub async fn get_latest_schedule_for(&self, date: NaiveDate) -> Result<Schedule, Error> {
+        self.schedule_for(date, cache::LATEST_TTL).await
+    }
+
+    async fn schedule_for(&self, date: NaiveDate, ttl: StdDuration) -> Result<Schedule, Error> {
+        self.cache
+            .get_or_fetch(
+                &cache::key(self.league(), &format!("schedule/{}", date)),
+                ttl,
+                || self.http.retry(|| self.league.get_schedule_for(date)),
+            )
+            .await
+    }
+
+    pub async fn get_game_content(
+        &self,
+        game_pk: u64,
+        game_date: DateTime<Utc>,
+    ) -> Result<GameContentResponse, Error> {
+        let ttl = if Utc::now() - game_date > Duration::days(1) {
+            cache::PAST_GAME_CONTENT_TTL
+        } else {
+            cache::GAME_CONTENT_TTL
         };
 
-        let teams = serde_json::from_slice(&serialized)?;
-        Ok(teams)
+        self.cache
+            .get_or_fetch(
+                &cache::key(self.league(), &format!("content/{}", game_pk)),
+                ttl,
+                || self.http.retry(|| self.league.get_game_content(game_pk)),
+            )
+            .await
+    }
+
+    pub async fn get_teams(&self) -> Result<Vec<Team>, Error> {
+        self.cache
+            .get_or_fetch(
+                &cache::key(self.league(), "teams"),
+                cache::TEAMS_TTL,
+                || self.http.retry(|| self.league.get_teams()),
+            )
+            .await
     }
 }
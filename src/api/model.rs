@@ -1,8 +1,8 @@
 use chrono::{DateTime, NaiveDate, Utc};
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 
 #[serde(rename_all = "camelCase")]
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Team {
     pub id: u32,
     pub name: String,
@@ -16,46 +16,74 @@ pub struct Team {
     pub active: bool,
 }
 
+impl From<&ScheduleGameTeamDetail> for Team {
+    /// Team that isn't returned with the league's teams, E.g. an All-Star or national team.
+    /// It has no abbreviation, so it can't be picked by the team commands
+    fn from(detail: &ScheduleGameTeamDetail) -> Self {
+        Team {
+            id: detail.id,
+            name: detail.name.clone(),
+            link: detail.link.clone(),
+            abbreviation: String::new(),
+            team_name: detail.name.clone(),
+            location_name: None,
+            first_year_of_play: None,
+            short_name: detail.name.clone(),
+            active: false,
+        }
+    }
+}
+
 #[serde(rename_all = "camelCase")]
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct ScheduleResponse {
     pub dates: Vec<Schedule>,
 }
 
 #[serde(rename_all = "camelCase")]
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Schedule {
     pub date: NaiveDate,
     pub games: Vec<ScheduleGame>,
 }
 
 #[serde(rename_all = "camelCase")]
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct ScheduleGame {
     pub game_pk: u64,
     pub link: String,
     pub date: DateTime<Utc>,
     pub game_type: String,
     pub season: String,
+    /// Which game of a doubleheader this is, 1 for all other games
+    pub game_number: Option<u32>,
     pub teams: ScheduleGameTeams,
+    pub status: Option<ScheduleGameStatus>,
 }
 
 #[serde(rename_all = "camelCase")]
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ScheduleGameStatus {
+    pub abstract_game_state: String,
+    pub detailed_state: String,
+}
+
+#[serde(rename_all = "camelCase")]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct ScheduleGameTeams {
     pub away: ScheduleGameTeam,
     pub home: ScheduleGameTeam,
 }
 
 #[serde(rename_all = "camelCase")]
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct ScheduleGameTeam {
     pub score: Option<u8>,
     pub detail: ScheduleGameTeamDetail,
 }
 
 #[serde(rename_all = "camelCase")]
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct ScheduleGameTeamDetail {
     pub id: u32,
     pub name: String,
@@ -63,51 +91,62 @@ pub struct ScheduleGameTeamDetail {
 }
 
 #[serde(rename_all = "camelCase")]
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct GameContentResponse {
     pub editorial: GameContentEditorial,
     pub media: GameContentMedia,
 }
 
 #[serde(rename_all = "camelCase")]
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct GameContentMedia {
     pub epg: Option<Vec<GameContentEpg>>,
 }
 
 #[serde(rename_all = "camelCase")]
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct GameContentEpg {
     pub title: String,
     pub items: Option<Vec<GameContentEpgItem>>,
 }
 
 #[serde(rename_all = "camelCase")]
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct GameContentEpgItem {
     pub media_feed_type: Option<String>,
+    /// Feed type of MLB audio items, which don't have `media_feed_type`
+    #[serde(rename = "type")]
+    pub item_type: Option<String>,
     pub call_letters: Option<String>,
+    pub language: Option<String>,
     pub media_state: Option<String>,
     pub id: Option<u32>,
     pub media_playback_id: Option<String>,
+    pub playbacks: Option<Vec<GameContentEpgPlayback>>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct GameContentEpgPlayback {
+    pub name: String,
+    pub url: String,
 }
 
 #[serde(rename_all = "camelCase")]
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct GameContentEditorial {
     #[serde(deserialize_with = "fail_as_none")]
     pub preview: Option<GameContentEditorialItem>,
 }
 
 #[serde(rename_all = "camelCase")]
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct GameContentEditorialItem {
     pub title: String,
     pub items: Option<Vec<GameContentEditorialItemArticle>>,
 }
 
 #[serde(rename_all = "camelCase")]
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct GameContentEditorialItemArticle {
     pub r#type: String,
     pub headline: String,
@@ -119,20 +158,20 @@ pub struct GameContentEditorialItemArticle {
 }
 
 #[serde(rename_all = "camelCase")]
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct GameContentArticleMedia {
     pub r#type: String,
     pub image: GameContentArticleMediaImage,
 }
 
 #[serde(rename_all = "camelCase")]
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct GameContentArticleMediaImage {
     pub cuts: GameContentArticleMediaImageCut,
 }
 
 #[serde(rename_all = "camelCase")]
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct GameContentArticleMediaImageCut {
     pub cut_2208_1242: GameContentArticleMediaImageCutDetail,
     pub cut_2048_1152: GameContentArticleMediaImageCutDetail,
@@ -152,7 +191,7 @@ pub struct GameContentArticleMediaImageCut {
 }
 
 #[serde(rename_all = "camelCase")]
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct GameContentArticleMediaImageCutDetail {
     pub aspect_ratio: String,
     pub width: u32,
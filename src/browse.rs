@@ -0,0 +1,547 @@
+use crate::{
+    exit_with_error,
+    opt::{Cdn, DateRange, Opt},
+    stream::{Game, LazyStream, MediaState, Stream},
+};
+use async_std::task;
+use chrono::Local;
+use crossterm::{
+    cursor::{Hide, MoveTo, Show},
+    event::{self, Event, KeyCode, KeyEvent, KeyModifiers},
+    execute, queue,
+    style::{Attribute, Print, SetAttribute},
+    terminal::{self, Clear, ClearType, EnterAlternateScreen, LeaveAlternateScreen},
+};
+use failure::Error;
+use std::io::{self, Write};
+
+const HELP: &str =
+    "↑↓ move  ←→ games / feeds  / search  p play  r record  c cast  y copy link  q quit";
+
+pub fn run(opts: Opt) {
+    let error_format = opts.error_format;
+    task::block_on(async {
+        if let Err(e) = process(opts).await {
+            exit_with_error(&e, error_format);
+        };
+    });
+}
+
+async fn process(opts: Opt) -> Result<(), Error> {
+    let lazy_stream = LazyStream::new(&opts).await?;
+    let mut browser = Browser::new(lazy_stream.games(), lazy_stream.date_range());
+
+    // The screen is restored before handing the stream off, so Streamlink's output shows
+    let picked = {
+        let _screen = Screen::enter()?;
+        browser.run(opts.cdn).await?
+    };
+
+    if let Some((action, game, stream)) = picked {
+        crate::streamlink::process_browsed(&opts, action, game, stream).await?;
+    }
+
+    Ok(())
+}
+
+/// What to do with the stream picked in the browser
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Action {
+    Play,
+    Record,
+    Cast,
+}
+
+/// Full-screen mode of the terminal, restored when dropped so an error doesn't leave
+/// the terminal in raw mode
+struct Screen;
+
+impl Screen {
+    fn enter() -> Result<Self, Error> {
+        terminal::enable_raw_mode()?;
+        execute!(io::stdout(), EnterAlternateScreen, Hide)?;
+        Ok(Screen)
+    }
+}
+
+impl Drop for Screen {
+    fn drop(&mut self) {
+        let _ = execute!(io::stdout(), Show, LeaveAlternateScreen);
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Focus {
+    Games,
+    Feeds,
+}
+
+/// Game with the streams and description fetched for it, once it's been selected
+struct Entry {
+    game: Game,
+    streams: Option<Vec<Stream>>,
+    description: Option<String>,
+}
+
+struct Browser {
+    entries: Vec<Entry>,
+    date_range: DateRange,
+    /// Entries matching the search, in order
+    visible: Vec<usize>,
+    search: String,
+    searching: bool,
+    game_idx: usize,
+    feed_idx: usize,
+    focus: Focus,
+    status: Option<String>,
+}
+
+impl Browser {
+    fn new(games: Vec<Game>, date_range: DateRange) -> Self {
+        let entries: Vec<_> = games
+            .into_iter()
+            .map(|game| Entry {
+                game,
+                streams: None,
+                description: None,
+            })
+            .collect();
+        let visible = (0..entries.len()).collect();
+
+        Browser {
+            entries,
+            date_range,
+            visible,
+            search: String::new(),
+            searching: false,
+            game_idx: 0,
+            feed_idx: 0,
+            focus: Focus::Games,
+            status: None,
+        }
+    }
+
+    /// Handle keys until a stream is picked, `None` if the browser is quit
+    async fn run(&mut self, cdn: Cdn) -> Result<Option<(Action, Game, Stream)>, Error> {
+        loop {
+            self.load_selected().await?;
+            self.draw()?;
+
+            // Anything else, E.g. the terminal being resized, just redraws
+            let key = match event::read()? {
+                Event::Key(key) => key,
+                _ => continue,
+            };
+
+            if self.searching {
+                self.search_key(key);
+                continue;
+            }
+            self.status = None;
+
+            let mut action = None;
+            match key.code {
+                KeyCode::Char('q') => return Ok(None),
+                KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                    return Ok(None)
+                }
+                KeyCode::Up | KeyCode::Char('k') => self.move_by(-1),
+                KeyCode::Down | KeyCode::Char('j') => self.move_by(1),
+                KeyCode::Right | KeyCode::Tab | KeyCode::Char('l') => self.focus_feeds(),
+                KeyCode::Left | KeyCode::BackTab | KeyCode::Backspace | KeyCode::Char('h') => {
+                    self.focus = Focus::Games
+                }
+                KeyCode::Esc if self.focus == Focus::Feeds => self.focus = Focus::Games,
+                KeyCode::Esc => {
+                    self.search.clear();
+                    self.apply_search();
+                }
+                KeyCode::Char('/') => {
+                    self.searching = true;
+                    self.focus = Focus::Games;
+                }
+                KeyCode::Enter if self.focus == Focus::Games => self.focus_feeds(),
+                KeyCode::Enter | KeyCode::Char('p') => action = Some(Action::Play),
+                KeyCode::Char('r') => action = Some(Action::Record),
+                KeyCode::Char('c') => action = Some(Action::Cast),
+                KeyCode::Char('y') => self.copy_link(cdn).await?,
+                _ => {}
+            }
+
+            // Nothing is picked while the game's feeds are still unavailable
+            if let Some(picked) = action.and_then(|action| self.pick(action)) {
+                return Ok(Some(picked));
+            }
+        }
+    }
+
+    fn search_key(&mut self, key: KeyEvent) {
+        match key.code {
+            KeyCode::Enter => self.searching = false,
+            KeyCode::Esc => {
+                self.searching = false;
+                self.search.clear();
+            }
+            KeyCode::Backspace => {
+                self.search.pop();
+            }
+            KeyCode::Char(c) => self.search.push(c),
+            _ => {}
+        }
+        self.apply_search();
+    }
+
+    /// Show only games a team of which matches the search
+    fn apply_search(&mut self) {
+        let search = self.search.to_lowercase();
+
+        self.visible = self
+            .entries
+            .iter()
+            .enumerate()
+            .filter(|(_, entry)| {
+                [&entry.game.home_team, &entry.game.away_team]
+                    .iter()
+                    .any(|team| {
+                        team.name.to_lowercase().contains(&search)
+                            || team.abbreviation.to_lowercase().contains(&search)
+                    })
+            })
+            .map(|(idx, _)| idx)
+            .collect();
+        self.game_idx = 0;
+        self.feed_idx = 0;
+    }
+
+    fn selected(&self) -> Option<&Entry> {
+        self.visible
+            .get(self.game_idx)
+            .map(|idx| &self.entries[*idx])
+    }
+
+    fn selected_mut(&mut self) -> Option<&mut Entry> {
+        let idx = *self.visible.get(self.game_idx)?;
+        self.entries.get_mut(idx)
+    }
+
+    fn feed_count(&self) -> usize {
+        self.selected()
+            .and_then(|entry| entry.streams.as_ref())
+            .map_or(0, Vec::len)
+    }
+
+    fn move_by(&mut self, delta: isize) {
+        let (idx, count) = match self.focus {
+            Focus::Games => (&mut self.game_idx, self.visible.len()),
+            Focus::Feeds => {
+                let count = self.feed_count();
+                (&mut self.feed_idx, count)
+            }
+        };
+        if count == 0 {
+            return;
+        }
+
+        *idx = (*idx as isize + delta).max(0).min(count as isize - 1) as usize;
+        if self.focus == Focus::Games {
+            self.feed_idx = 0;
+        }
+    }
+
+    fn focus_feeds(&mut self) {
+        if self.feed_count() > 0 {
+            self.focus = Focus::Feeds;
+        }
+    }
+
+    /// Fetch the streams and description of the selected game, the first time it's selected
+    async fn load_selected(&mut self) -> Result<(), Error> {
+        if self
+            .selected()
+            .map_or(true, |entry| entry.streams.is_some())
+        {
+            return Ok(());
+        }
+
+        self.status = Some(String::from("Loading feeds..."));
+        self.draw()?;
+
+        let entry = self.selected_mut().unwrap();
+        let mut streams: Vec<Stream> = vec![];
+        let result = async {
+            streams.extend(
+                entry
+                    .game
+                    .streams()
+                    .await?
+                    .into_iter()
+                    .map(|(_, stream)| stream),
+            );
+            streams.append(&mut entry.game.audio_streams().await?);
+            streams.append(&mut entry.game.vod_streams().await?);
+            Ok::<_, Error>(())
+        }
+        .await;
+        entry.description = entry.game.description().await;
+        entry.streams = Some(streams);
+
+        self.status = result.err().map(|e| format!("Couldn't load feeds: {}", e));
+        Ok(())
+    }
+
+    fn pick(&self, action: Action) -> Option<(Action, Game, Stream)> {
+        let entry = self.selected()?;
+        let stream = entry.streams.as_ref()?.get(self.feed_idx)?;
+
+        Some((action, entry.game.clone(), stream.clone()))
+    }
+
+    /// Copy the master link of the selected feed to the clipboard with an OSC 52 escape
+    /// sequence, falling back to the host link if it isn't available yet
+    async fn copy_link(&mut self, cdn: Cdn) -> Result<(), Error> {
+        let feed_idx = self.feed_idx;
+        let stream = match self
+            .selected_mut()
+            .and_then(|entry| entry.streams.as_mut())
+            .and_then(|streams| streams.get_mut(feed_idx))
+        {
+            Some(stream) => stream,
+            None => return Ok(()),
+        };
+
+        let link = match stream.master_link(cdn).await {
+            Ok(link) => link,
+            Err(_) => stream.host_link(cdn),
+        };
+
+        execute!(
+            io::stdout(),
+            Print(format!("\x1b]52;c;{}\x07", base64::encode(&link)))
+        )?;
+        self.status = Some(format!("Copied {}", link));
+        Ok(())
+    }
+
+    fn draw(&self) -> Result<(), Error> {
+        let (width, height) = terminal::size()?;
+        let (width, height) = (width as usize, height as usize);
+        let games_width = (width * 2 / 5).max(32).min(width);
+        let detail_x = games_width + 2;
+        let detail_width = width.saturating_sub(detail_x);
+        let list_height = height.saturating_sub(4);
+
+        let mut stdout = io::stdout();
+        queue!(stdout, Clear(ClearType::All))?;
+
+        let title = format!("lazystream - games for {}", self.date_range);
+        line(&mut stdout, 0, 0, width, &title, Some(Attribute::Bold))?;
+
+        // Games, scrolled so the selected one is always shown
+        let offset = (self.game_idx + 1).saturating_sub(list_height);
+        for (row, idx) in self
+            .visible
+            .iter()
+            .enumerate()
+            .skip(offset)
+            .take(list_height)
+        {
+            let highlight = match self.focus {
+                _ if row != self.game_idx => None,
+                Focus::Games => Some(Attribute::Reverse),
+                Focus::Feeds => Some(Attribute::Bold),
+            };
+            let text = self.game_row(&self.entries[*idx].game);
+            line(
+                &mut stdout,
+                0,
+                2 + row - offset,
+                games_width,
+                &text,
+                highlight,
+            )?;
+        }
+        if self.visible.is_empty() {
+            line(&mut stdout, 0, 2, games_width, "No games", None)?;
+        }
+
+        if let Some(entry) = self.selected() {
+            self.draw_detail(&mut stdout, entry, detail_x, detail_width, list_height)?;
+        }
+
+        let status = if self.searching {
+            format!("Search: {}_", self.search)
+        } else if let Some(status) = &self.status {
+            status.clone()
+        } else if !self.search.is_empty() {
+            format!("Search: {}  (Esc to clear)  {}", self.search, HELP)
+        } else {
+            HELP.to_owned()
+        };
+        line(
+            &mut stdout,
+            0,
+            height.saturating_sub(1),
+            width,
+            &status,
+            Some(Attribute::Dim),
+        )?;
+
+        stdout.flush()?;
+        Ok(())
+    }
+
+    /// Teams, start time, state and description of the game, then its feeds
+    fn draw_detail(
+        &self,
+        stdout: &mut io::Stdout,
+        entry: &Entry,
+        x: usize,
+        width: usize,
+        height: usize,
+    ) -> Result<(), Error> {
+        let game = &entry.game;
+
+        let mut rows = vec![
+            (
+                format!(
+                    "{} @ {}{}",
+                    game.away_team.name,
+                    game.home_team.name,
+                    game.game_number_suffix()
+                ),
+                Some(Attribute::Bold),
+            ),
+            (
+                game.game_date
+                    .with_timezone(&Local)
+                    .format("%A %B %-d, %-I:%M %p")
+                    .to_string(),
+                None,
+            ),
+        ];
+        if let Some(status) = &game.status {
+            rows.push((status.detailed_state.clone(), None));
+        }
+        if let Some(description) = &entry.description {
+            rows.push((String::new(), None));
+            rows.extend(wrap(description, width).into_iter().map(|row| (row, None)));
+        }
+        rows.push((String::new(), None));
+        rows.push((String::from("Feeds"), Some(Attribute::Underlined)));
+
+        for (row, (text, attribute)) in rows.iter().enumerate() {
+            line(stdout, x, 2 + row, width, text, *attribute)?;
+        }
+
+        let feeds_y = 2 + rows.len();
+        match &entry.streams {
+            Some(streams) if !streams.is_empty() => {
+                for (idx, stream) in streams
+                    .iter()
+                    .enumerate()
+                    .take(height.saturating_sub(rows.len()))
+                {
+                    let highlight = match self.focus {
+                        _ if idx != self.feed_idx => None,
+                        Focus::Feeds => Some(Attribute::Reverse),
+                        Focus::Games => Some(Attribute::Bold),
+                    };
+                    let text = format!("{} {}", feed_marker(stream), stream.label());
+                    line(stdout, x, feeds_y + idx, width, &text, highlight)?;
+                }
+            }
+            Some(_) => line(stdout, x, feeds_y, width, "No feeds", None)?,
+            None => line(stdout, x, feeds_y, width, "Loading...", None)?,
+        }
+
+        Ok(())
+    }
+
+    /// Marker, start time and teams of a game, E.g. `● 7:00 PM  BOS @ NYR`
+    fn game_row(&self, game: &Game) -> String {
+        let marker = if game.is_live() {
+            '●'
+        } else if game.ended_state().is_some() {
+            '✓'
+        } else {
+            ' '
+        };
+
+        // Games on different days need the day shown to tell them apart
+        let time_format = if self.date_range.is_single_day() {
+            "%-I:%M %p"
+        } else {
+            "%a %-d %-I:%M %p"
+        };
+
+        format!(
+            "{} {:>8}  {} @ {}{}",
+            marker,
+            game.game_date
+                .with_timezone(&Local)
+                .format(time_format)
+                .to_string(),
+            game.away_team.abbreviation,
+            game.home_team.abbreviation,
+            game.game_number_suffix()
+        )
+    }
+}
+
+/// Availability marker of a feed, live feeds can be played now
+fn feed_marker(stream: &Stream) -> char {
+    match stream.media_state {
+        MediaState::Live => '●',
+        MediaState::Archived => '✓',
+        MediaState::Upcoming => '○',
+        MediaState::Unknown => ' ',
+    }
+}
+
+/// Print `text` at a position, cut to `width` and padded so highlights fill the width
+fn line(
+    stdout: &mut io::Stdout,
+    x: usize,
+    y: usize,
+    width: usize,
+    text: &str,
+    attribute: Option<Attribute>,
+) -> Result<(), Error> {
+    let text: String = text.chars().take(width).collect();
+    let text = format!("{:width$}", text, width = width);
+
+    queue!(stdout, MoveTo(x as u16, y as u16))?;
+    match attribute {
+        Some(attribute) => queue!(
+            stdout,
+            SetAttribute(attribute),
+            Print(text),
+            SetAttribute(Attribute::Reset)
+        )?,
+        None => queue!(stdout, Print(text))?,
+    }
+
+    Ok(())
+}
+
+/// Wrap text on whitespace into lines of at most `width` characters
+fn wrap(text: &str, width: usize) -> Vec<String> {
+    let mut lines = vec![];
+    let mut current = String::new();
+
+    for word in text.split_whitespace() {
+        if !current.is_empty() && current.chars().count() + 1 + word.chars().count() > width {
+            lines.push(std::mem::take(&mut current));
+        }
+        if !current.is_empty() {
+            current.push(' ');
+        }
+        current.push_str(word);
+    }
+    if !current.is_empty() {
+        lines.push(current);
+    }
+
+    lines
+}
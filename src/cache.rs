@@ -0,0 +1,119 @@
+use crate::{error::LazyStreamError, league::LeagueProvider};
+use async_std::fs;
+use failure::Error;
+use futures::Future;
+use serde::{de::DeserializeOwned, Serialize};
+use std::{
+    path::PathBuf,
+    time::{Duration, SystemTime},
+};
+
+/// Teams only change between seasons
+pub const TEAMS_TTL: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+/// Schedules of days that are over only change if a game is rescheduled
+pub const PAST_SCHEDULE_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+/// Schedules of today and upcoming days change as games start and end
+pub const SCHEDULE_TTL: Duration = Duration::from_secs(2 * 60);
+/// Game content of finished games only changes when highlights are published
+pub const PAST_GAME_CONTENT_TTL: Duration = Duration::from_secs(6 * 60 * 60);
+/// Game content of upcoming and live games changes as streams become available
+pub const GAME_CONTENT_TTL: Duration = Duration::from_secs(2 * 60);
+/// Responses that have to be up to date are fetched every time, unless offline
+pub const LATEST_TTL: Duration = Duration::from_secs(0);
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CacheMode {
+    /// Use cached responses until they expire
+    Normal,
+    /// Ignore cached responses, but still cache new ones
+    Refresh,
+    /// Only use cached responses, even if they've expired
+    Offline,
+}
+
+/// Key of the league's response `name`, responses are cached per league
+pub fn key(league: &dyn LeagueProvider, name: &str) -> String {
+    format!("{}/{}", league.sport().to_string().to_lowercase(), name)
+}
+
+/// On-disk cache of API responses, stored as json under the user's cache directory
+pub struct Cache {
+    dir: Option<PathBuf>,
+    mode: CacheMode,
+}
+
+impl Cache {
+    pub fn new(mode: CacheMode) -> Self {
+        Cache {
+            dir: dirs::cache_dir().map(|dir| dir.join("lazystream")),
+            mode,
+        }
+    }
+
+    /// Get `key` from the cache if it hasn't expired, otherwise `fetch` it and cache it.
+    /// Caching is best effort, if the cache can't be read or written `fetch` is used
+    pub async fn get_or_fetch<T, F, Fut>(
+        &self,
+        key: &str,
+        ttl: Duration,
+        fetch: F,
+    ) -> Result<T, Error>
+    where
+        T: Serialize + DeserializeOwned,
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<T, Error>>,
+    {
+        match self.mode {
+            CacheMode::Normal => {
+                if let Some(value) = self.read(key, Some(ttl)).await {
+                    return Ok(value);
+                }
+            }
+            CacheMode::Offline => {
+                return match self.read(key, None).await {
+                    Some(value) => Ok(value),
+                    None => Err(LazyStreamError::NotCached(key.to_owned()).into()),
+                };
+            }
+            CacheMode::Refresh => {}
+        }
+
+        let value = fetch().await?;
+        self.write(key, &value).await;
+
+        Ok(value)
+    }
+
+    /// Cached value of `key`, if it exists and is younger than `ttl`
+    async fn read<T: DeserializeOwned>(&self, key: &str, ttl: Option<Duration>) -> Option<T> {
+        let path = self.path(key)?;
+
+        if let Some(ttl) = ttl {
+            let modified = fs::metadata(&path).await.ok()?.modified().ok()?;
+            let age = SystemTime::now()
+                .duration_since(modified)
+                .unwrap_or_default();
+            if age > ttl {
+                return None;
+            }
+        }
+
+        let bytes = fs::read(&path).await.ok()?;
+        serde_json::from_slice(&bytes).ok()
+    }
+
+    async fn write<T: Serialize>(&self, key: &str, value: &T) {
+        if let (Some(path), Ok(bytes)) = (self.path(key), serde_json::to_vec(value)) {
+            if let Some(parent) = path.parent() {
+                let _ = fs::create_dir_all(parent).await;
+            }
+            let _ = fs::write(&path, bytes).await;
+        }
+    }
+
+    fn path(&self, key: &str) -> Option<PathBuf> {
+        self.dir
+            .as_ref()
+            .map(|dir| dir.join(format!("{}.json", key)))
+    }
+}
@@ -0,0 +1,570 @@
+use crate::{
+    error::LazyStreamError,
+    exit_with_error, log_error,
+    opt::{
+        CastCommand, Cdn, Command, ConfigCommand, Opt, PlayCommand, Quality, QualityFallback,
+        RecordCommand, Sport,
+    },
+    provider::HostTemplate,
+};
+use failure::{bail, Error, ResultExt};
+use http::Uri;
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, env, fs, path::PathBuf, str::FromStr};
+use structopt::clap::ArgMatches;
+
+/// Setting of the config file, it replaces the default of the arguments it's for. Defaults
+/// are left to the options, so they're only defined once
+struct Setting {
+    key: &'static str,
+    /// Names of the arguments the setting is for, in any of the subcommands
+    args: &'static [&'static str],
+    /// Whether the value is a path, which can start with `~/`
+    is_path: bool,
+    validate: fn(&str) -> Result<(), Error>,
+}
+
+const SETTINGS: &[Setting] = &[
+    Setting {
+        key: "sport",
+        args: &["sport"],
+        is_path: false,
+        validate: validate::<Sport>,
+    },
+    Setting {
+        key: "cdn",
+        args: &["cdn"],
+        is_path: false,
+        validate: validate::<Cdn>,
+    },
+    Setting {
+        key: "quality",
+        args: &["quality"],
+        is_path: false,
+        validate: validate::<Quality>,
+    },
+    Setting {
+        key: "quality_fallback",
+        args: &["quality-fallback"],
+        is_path: false,
+        validate: validate::<QualityFallback>,
+    },
+    Setting {
+        key: "host",
+        args: &["host"],
+        is_path: false,
+        validate: validate::<String>,
+    },
+    Setting {
+        key: "host_template",
+        args: &["host-template"],
+        is_path: false,
+        validate: validate::<HostTemplate>,
+    },
+    Setting {
+        key: "timeout",
+        args: &["timeout"],
+        is_path: false,
+        validate: validate::<u64>,
+    },
+    Setting {
+        key: "retries",
+        args: &["retries"],
+        is_path: false,
+        validate: validate::<u32>,
+    },
+    Setting {
+        key: "proxy",
+        args: &["proxy"],
+        is_path: false,
+        validate: validate::<Uri>,
+    },
+    Setting {
+        key: "custom_player",
+        args: &["PATH"],
+        is_path: true,
+        validate: validate::<PathBuf>,
+    },
+    Setting {
+        key: "output_dir",
+        args: &["output", "OUTPUT_DIR"],
+        is_path: true,
+        validate: validate::<PathBuf>,
+    },
+    Setting {
+        key: "cast_host",
+        args: &["cast-host", "CHROMECAST_HOST"],
+        is_path: false,
+        validate: validate::<String>,
+    },
+    Setting {
+        key: "audio_source",
+        args: &["audio-source"],
+        is_path: false,
+        validate: validate::<String>,
+    },
+];
+
+const TEMPLATE: &str = r#"# lazystream settings, run `lazystream config show` to list them all.
+# Flags and LAZYSTREAM_* environment variables take precedence over these.
+#
+# sport = "mlb"
+# quality = "720p60"
+# output_dir = "~/Videos"
+#
+# Profiles are used with --profile E.g. `lazystream --profile living-room cast team VGK`
+#
+# [profiles.living-room]
+# cast_host = "192.168.0.100"
+# quality = "1080p60"
+# audio_source = "en"
+"#;
+
+fn validate<T>(value: &str) -> Result<(), Error>
+where
+    T: FromStr,
+    T::Err: Into<Error>,
+{
+    T::from_str(value).map(drop).map_err(Into::into)
+}
+
+fn setting(key: &str) -> Result<&'static Setting, Error> {
+    SETTINGS
+        .iter()
+        .find(|setting| setting.key == key)
+        .ok_or_else(|| {
+            let keys: Vec<_> = SETTINGS.iter().map(|setting| setting.key).collect();
+            LazyStreamError::UnknownSetting(key.to_owned(), keys.join(", ")).into()
+        })
+}
+
+/// Environment variable a setting is passed as, E.g. `LAZYSTREAM_CAST_HOST`
+fn env_var(key: &str) -> String {
+    format!("LAZYSTREAM_{}", key.to_uppercase())
+}
+
+/// Path of the config file, in the user's config directory
+pub fn path() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join("lazystream").join("config.toml"))
+}
+
+/// Where the effective value of a setting comes from
+#[derive(Debug, Clone, PartialEq)]
+enum Source {
+    Config,
+    Profile(String),
+}
+
+impl std::fmt::Display for Source {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Source::Config => write!(f, "config"),
+            Source::Profile(profile) => write!(f, "profile {}", profile),
+        }
+    }
+}
+
+type Settings = BTreeMap<String, toml::Value>;
+
+/// Contents of the config file. Settings at the top are used by default, settings of a
+/// profile replace them when the profile is used
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Config {
+    #[serde(flatten)]
+    settings: Settings,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    profiles: BTreeMap<String, Settings>,
+}
+
+impl Config {
+    /// Read the config file, an empty config if there isn't one. Fails if any setting is
+    /// invalid
+    pub fn load() -> Result<Config, Error> {
+        let config = Config::read()?;
+        match config.invalid_settings().into_iter().next() {
+            Some(e) => Err(e),
+            None => Ok(config),
+        }
+    }
+
+    /// Read the config file for the config command, which has to work while the file is
+    /// invalid so it can be fixed. Invalid settings are warned about and left out
+    fn load_lenient() -> Result<Config, Error> {
+        let mut config = Config::read()?;
+
+        for e in config.invalid_settings() {
+            log_error(&e);
+        }
+        config
+            .settings
+            .retain(|key, value| check(key, value).is_ok());
+        for settings in config.profiles.values_mut() {
+            settings.retain(|key, value| check(key, value).is_ok());
+        }
+
+        Ok(config)
+    }
+
+    /// Read the config file without checking its settings
+    fn read() -> Result<Config, Error> {
+        let path = match path() {
+            Some(path) if path.is_file() => path,
+            _ => return Ok(Config::default()),
+        };
+        let context = || LazyStreamError::InvalidConfig(path.display().to_string());
+
+        let contents = fs::read_to_string(&path).with_context(|_| context())?;
+        let config = toml::from_str(&contents).with_context(|_| context())?;
+
+        Ok(config)
+    }
+
+    /// Error of each setting that isn't a setting or has an invalid value
+    fn invalid_settings(&self) -> Vec<Error> {
+        let profiles = self
+            .profiles
+            .iter()
+            .map(|(profile, settings)| (Some(profile), settings));
+
+        let mut errors = vec![];
+        for (profile, settings) in std::iter::once((None, &self.settings)).chain(profiles) {
+            for (key, value) in settings {
+                let name = match profile {
+                    Some(profile) => format!("profiles.{}.{}", profile, key),
+                    None => key.clone(),
+                };
+                if let Err(e) = check(key, value) {
+                    errors.push(e.context(LazyStreamError::InvalidSetting(name)).into());
+                }
+            }
+        }
+
+        errors
+    }
+
+    fn save(&self) -> Result<PathBuf, Error> {
+        let path = match path() {
+            Some(path) => path,
+            None => bail!("Couldn't find the user's config directory"),
+        };
+
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(&path, toml::to_string(self)?)?;
+
+        Ok(path)
+    }
+
+    pub fn has_profile(&self, profile: &str) -> bool {
+        self.profiles.contains_key(profile)
+    }
+
+    /// Value and source of each setting of the config file and profile, the profile's
+    /// settings replacing those of the config file
+    fn layered(&self, profile: Option<&str>) -> BTreeMap<String, (String, Source)> {
+        let mut layered = BTreeMap::new();
+
+        for (key, value) in &self.settings {
+            layered.insert(key.clone(), (value_string(value), Source::Config));
+        }
+        if let Some((profile, settings)) =
+            profile.and_then(|profile| self.profiles.get_key_value(profile))
+        {
+            for (key, value) in settings {
+                let source = Source::Profile(profile.clone());
+                layered.insert(key.clone(), (value_string(value), source));
+            }
+        }
+
+        layered
+    }
+
+    /// Merge the settings into the options. A setting only replaces the default of its
+    /// arguments, so arguments passed as a flag or environment variable take precedence
+    pub fn apply(&self, opts: &mut Opt, matches: &ArgMatches) -> Result<(), Error> {
+        for (key, (value, _)) in self.layered(opts.profile.as_deref()) {
+            let setting = setting(&key)?;
+            let passed = setting.args.iter().any(|arg| passed(matches, arg));
+            if passed || env::var_os(env_var(&key)).is_some() {
+                continue;
+            }
+
+            let expanded = match (setting.is_path, value.strip_prefix("~/"), dirs::home_dir()) {
+                (true, Some(rest), Some(home)) => Some(home.join(rest).display().to_string()),
+                _ => None,
+            };
+
+            apply_setting(opts, &key, expanded.as_deref().unwrap_or(&value))
+                .context(LazyStreamError::InvalidSetting(key.clone()))?;
+        }
+
+        Ok(())
+    }
+
+    /// Set or remove a setting, of the profile if one is specified
+    fn set(&mut self, profile: Option<&str>, key: &str, value: Option<&str>) -> Result<(), Error> {
+        let settings = match profile {
+            Some(profile) => self.profiles.entry(profile.to_owned()).or_default(),
+            None => &mut self.settings,
+        };
+
+        match value {
+            Some(value) => {
+                let setting = setting(key)?;
+                (setting.validate)(value)
+                    .context(LazyStreamError::InvalidSetting(key.to_owned()))?;
+
+                // Numbers are saved as numbers, so the file reads naturally
+                let value = match value.parse::<i64>() {
+                    Ok(number) if !setting.is_path => toml::Value::Integer(number),
+                    _ => toml::Value::String(value.to_owned()),
+                };
+                settings.insert(key.to_owned(), value);
+            }
+            // Keys that aren't settings can be removed too, they only make the file invalid
+            None => {
+                if settings.remove(key).is_none() {
+                    setting(key)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Check the key and value of a setting of the config file
+fn check(key: &str, value: &toml::Value) -> Result<(), Error> {
+    (setting(key)?.validate)(&value_string(value))
+}
+
+/// Whether an argument was passed to the command or any of its subcommands
+fn passed(matches: &ArgMatches, arg: &str) -> bool {
+    matches.occurrences_of(arg) > 0 || matches.subcommand().1.map_or(false, |sub| passed(sub, arg))
+}
+
+/// Set the option of a setting, to the options of the command that has it
+fn apply_setting(opts: &mut Opt, key: &str, value: &str) -> Result<(), Error> {
+    match (key, &mut opts.command) {
+        ("sport", _) => opts.sport = value.parse()?,
+        ("cdn", _) => opts.cdn = value.parse()?,
+        ("quality", _) => opts.quality = Some(value.parse()?),
+        ("quality_fallback", _) => opts.quality_fallback = value.parse()?,
+        ("host", _) => opts.host = value.to_owned(),
+        ("host_template", _) => opts.host_template = value.parse()?,
+        ("timeout", _) => opts.timeout = value.parse()?,
+        ("retries", _) => opts.retries = value.parse()?,
+        (
+            "proxy",
+            Command::Play {
+                command: PlayCommand::Select { proxy, .. },
+            },
+        )
+        | (
+            "proxy",
+            Command::Play {
+                command: PlayCommand::Team { proxy, .. },
+            },
+        )
+        | (
+            "proxy",
+            Command::Record {
+                command: RecordCommand::Select { proxy, .. },
+            },
+        )
+        | (
+            "proxy",
+            Command::Record {
+                command: RecordCommand::Team { proxy, .. },
+            },
+        )
+        | (
+            "proxy",
+            Command::Cast {
+                command: CastCommand::Select { proxy, .. },
+            },
+        )
+        | (
+            "proxy",
+            Command::Cast {
+                command: CastCommand::Team { proxy, .. },
+            },
+        ) => *proxy = Some(value.parse()?),
+        ("custom_player", Command::Browse { custom_player, .. })
+        | (
+            "custom_player",
+            Command::Play {
+                command: PlayCommand::Select { custom_player, .. },
+            },
+        )
+        | (
+            "custom_player",
+            Command::Play {
+                command: PlayCommand::Team { custom_player, .. },
+            },
+        ) => *custom_player = Some(PathBuf::from(value)),
+        ("output_dir", Command::Browse { output, .. }) => *output = PathBuf::from(value),
+        (
+            "output_dir",
+            Command::Record {
+                command: RecordCommand::Select { output, .. },
+            },
+        )
+        | (
+            "output_dir",
+            Command::Record {
+                command: RecordCommand::Team { output, .. },
+            },
+        ) => *output = Some(PathBuf::from(value)),
+        ("cast_host", Command::Browse { cast_host, .. })
+        | (
+            "cast_host",
+            Command::Cast {
+                command: CastCommand::Team { cast_host, .. },
+            },
+        ) => *cast_host = Some(value.to_owned()),
+        (
+            "audio_source",
+            Command::Record {
+                command: RecordCommand::Select { audio_source, .. },
+            },
+        )
+        | (
+            "audio_source",
+            Command::Record {
+                command: RecordCommand::Team { audio_source, .. },
+            },
+        )
+        | (
+            "audio_source",
+            Command::Cast {
+                command: CastCommand::Select { audio_source, .. },
+            },
+        )
+        | (
+            "audio_source",
+            Command::Cast {
+                command: CastCommand::Team { audio_source, .. },
+            },
+        ) => *audio_source = Some(value.to_owned()),
+        // Settings of arguments the command doesn't have
+        _ => {}
+    }
+
+    Ok(())
+}
+
+fn value_string(value: &toml::Value) -> String {
+    match value {
+        toml::Value::String(value) => value.clone(),
+        value => value.to_string(),
+    }
+}
+
+pub fn run(opts: Opt) {
+    if let Err(e) = process(&opts) {
+        exit_with_error(&e, opts.error_format);
+    }
+}
+
+fn process(opts: &Opt) -> Result<(), Error> {
+    let command = match &opts.command {
+        Command::Config { command } => command,
+        _ => bail!("Wrong command for module"),
+    };
+    let profile = opts.profile.as_deref();
+
+    match command {
+        ConfigCommand::Show => show(profile)?,
+        ConfigCommand::Path => match path() {
+            Some(path) => println!("{}", path.display()),
+            None => bail!("Couldn't find the user's config directory"),
+        },
+        // Read without checking it, so invalid settings can be fixed
+        ConfigCommand::Set { key, value } => {
+            let mut config = Config::read()?;
+            config.set(profile, key, Some(value))?;
+            let path = config.save()?;
+            println!("Set {} = {} in {}", key, value, path.display());
+        }
+        ConfigCommand::Unset { key } => {
+            let mut config = Config::read()?;
+            config.set(profile, key, None)?;
+            let path = config.save()?;
+            println!("Removed {} from {}", key, path.display());
+        }
+        ConfigCommand::Edit => edit()?,
+    }
+
+    Ok(())
+}
+
+/// Print every setting with its effective value and where it comes from
+fn show(profile: Option<&str>) -> Result<(), Error> {
+    let config = Config::load_lenient()?;
+    let layered = config.layered(profile);
+
+    if let Some(path) = path() {
+        println!("Config file: {}", path.display());
+    }
+    if let Some(profile) = profile {
+        println!("Profile: {}", profile);
+    }
+    println!();
+
+    for setting in SETTINGS {
+        let env_value = env::var(env_var(setting.key)).ok();
+        let (value, source) = match (env_value, layered.get(setting.key)) {
+            (Some(value), _) => (value, format!("env {}", env_var(setting.key))),
+            (None, Some((value, source))) => (value.clone(), source.to_string()),
+            (None, None) => (String::from("(default)"), String::new()),
+        };
+
+        println!("{:<18}{:<32}{}", setting.key, value, source);
+    }
+
+    Ok(())
+}
+
+/// Open the config file in the user's editor, creating it from a template if it
+/// doesn't exist yet
+fn edit() -> Result<(), Error> {
+    let path = match path() {
+        Some(path) => path,
+        None => bail!("Couldn't find the user's config directory"),
+    };
+
+    if !path.exists() {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(&path, TEMPLATE)?;
+    }
+
+    let editor = env::var("VISUAL")
+        .or_else(|_| env::var("EDITOR"))
+        .unwrap_or_else(|_| {
+            if cfg!(target_os = "windows") {
+                String::from("notepad")
+            } else {
+                String::from("vi")
+            }
+        });
+
+    // Editors are often configured with arguments E.g. `code --wait`
+    let mut editor_args = editor.split_whitespace();
+    let program = editor_args.next().unwrap_or("vi");
+    let status = std::process::Command::new(program)
+        .args(editor_args)
+        .arg(&path)
+        .status()
+        .with_context(|_| format!("Couldn't run editor {}", program))?;
+    if !status.success() {
+        bail!("Editor {} exited with {}", program, status);
+    }
+
+    Config::load()?;
+    println!("Saved {}", path.display());
+    Ok(())
+}
@@ -0,0 +1,162 @@
+use failure::{Context, Error, Fail};
+
+/// Errors that can be told apart by the process exit code
+///
+/// | Code | Meaning                                                |
+/// |------|--------------------------------------------------------|
+/// | 0    | Success                                                |
+/// | 1    | Unexpected error                                       |
+/// | 2    | No game found for the team on the date or selection    |
+/// | 3    | Team doesn't exist or matches more than one team       |
+/// | 4    | Stream, feed or quality isn't available                |
+/// | 5    | Game is over, postponed or cancelled                   |
+/// | 6    | Network request failed, or response not cached offline |
+/// | 7    | Streamlink or VLC couldn't be found                    |
+/// | 8    | Streamlink exited with an error                        |
+/// | 9    | Output directory or cast device problem                |
+/// | 10   | Config file, profile or setting is invalid             |
+#[derive(Debug, Fail)]
+pub enum LazyStreamError {
+    #[fail(display = "There are no games for {} on {}", _0, _1)]
+    NoGame(String, String),
+    #[fail(display = "No game matches {}", _0)]
+    NoMatchingGame(String),
+    #[fail(display = "More than one game matches {}: {}", _0, _1)]
+    AmbiguousGame(String, String),
+    #[fail(display = "Team {} does not exist", _0)]
+    UnknownTeam(String),
+    #[fail(display = "Team {} does not exist, did you mean: {}", _0, _1)]
+    TeamSuggestions(String, String),
+    #[fail(display = "{} matches more than one team, did you mean: {}", _0, _1)]
+    AmbiguousTeam(String, String),
+    #[fail(display = "No streams available for that game")]
+    NoStreams,
+    #[fail(display = "Stream not available yet")]
+    StreamNotAvailable,
+    #[fail(
+        display = "Feed type {} not available for that game, available feeds are: {}",
+        _0, _1
+    )]
+    FeedNotAvailable(String, String),
+    #[fail(display = "Link doesn't exist for specified quality")]
+    QualityNotAvailable,
+    #[fail(display = "Master m3u8 is not valid")]
+    InvalidPlaylist,
+    #[fail(display = "No CDNs left to fail over to")]
+    NoCdnAvailable,
+    #[fail(
+        display = "Stream still not available {} hours after the game started",
+        _0
+    )]
+    StreamTimedOut(i64),
+    #[fail(display = "Game is {}, stream will not become available", _0)]
+    GameEnded(String),
+    #[fail(display = "Network request failed")]
+    Network,
+    #[fail(
+        display = "No cached response for {}, can't fetch it while offline",
+        _0
+    )]
+    NotCached(String),
+    #[fail(
+        display = "Could not find and run {}. Please ensure it is installed \
+                   and accessible from your PATH",
+        _0
+    )]
+    MissingDependency(&'static str),
+    #[fail(display = "StreamLink failed")]
+    StreamlinkFailed,
+    #[fail(display = "Output diretory does not exist, please create it")]
+    InvalidOutput,
+    #[fail(display = "No castable devices found on LAN")]
+    NoCastDevices,
+    #[fail(display = "mDNS discovery failed")]
+    CastDiscovery,
+    #[fail(display = "Config file {} is invalid", _0)]
+    InvalidConfig(String),
+    #[fail(display = "Profile {} doesn't exist in the config file", _0)]
+    UnknownProfile(String),
+    #[fail(display = "{} isn't a setting, settings are: {}", _0, _1)]
+    UnknownSetting(String, String),
+    #[fail(display = "Invalid value for setting {}", _0)]
+    InvalidSetting(String),
+}
+
+impl LazyStreamError {
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            LazyStreamError::NoGame(..)
+            | LazyStreamError::NoMatchingGame(_)
+            | LazyStreamError::AmbiguousGame(..) => 2,
+            LazyStreamError::UnknownTeam(_)
+            | LazyStreamError::TeamSuggestions(..)
+            | LazyStreamError::AmbiguousTeam(..) => 3,
+            LazyStreamError::NoStreams
+            | LazyStreamError::StreamNotAvailable
+            | LazyStreamError::FeedNotAvailable(..)
+            | LazyStreamError::QualityNotAvailable
+            | LazyStreamError::InvalidPlaylist
+            | LazyStreamError::NoCdnAvailable
+            | LazyStreamError::StreamTimedOut(_) => 4,
+            LazyStreamError::GameEnded(_) => 5,
+            LazyStreamError::Network | LazyStreamError::NotCached(_) => 6,
+            LazyStreamError::MissingDependency(_) => 7,
+            LazyStreamError::StreamlinkFailed => 8,
+            LazyStreamError::InvalidOutput
+            | LazyStreamError::NoCastDevices
+            | LazyStreamError::CastDiscovery => 9,
+            LazyStreamError::InvalidConfig(_)
+            | LazyStreamError::UnknownProfile(_)
+            | LazyStreamError::UnknownSetting(..)
+            | LazyStreamError::InvalidSetting(_) => 10,
+        }
+    }
+
+    /// Stable identifier of the error, used for json output
+    pub fn kind(&self) -> &'static str {
+        match self {
+            LazyStreamError::NoGame(..) => "no_game",
+            LazyStreamError::NoMatchingGame(_) => "no_matching_game",
+            LazyStreamError::AmbiguousGame(..) => "ambiguous_game",
+            LazyStreamError::UnknownTeam(_) | LazyStreamError::TeamSuggestions(..) => {
+                "unknown_team"
+            }
+            LazyStreamError::AmbiguousTeam(..) => "ambiguous_team",
+            LazyStreamError::NoStreams => "no_streams",
+            LazyStreamError::StreamNotAvailable => "stream_not_available",
+            LazyStreamError::FeedNotAvailable(..) => "feed_not_available",
+            LazyStreamError::QualityNotAvailable => "quality_not_available",
+            LazyStreamError::InvalidPlaylist => "invalid_playlist",
+            LazyStreamError::NoCdnAvailable => "no_cdn_available",
+            LazyStreamError::StreamTimedOut(_) => "stream_timed_out",
+            LazyStreamError::GameEnded(_) => "game_ended",
+            LazyStreamError::Network => "network",
+            LazyStreamError::NotCached(_) => "not_cached",
+            LazyStreamError::MissingDependency(_) => "missing_dependency",
+            LazyStreamError::StreamlinkFailed => "streamlink_failed",
+            LazyStreamError::InvalidOutput => "invalid_output",
+            LazyStreamError::NoCastDevices => "no_cast_devices",
+            LazyStreamError::CastDiscovery => "cast_discovery",
+            LazyStreamError::InvalidConfig(_) => "invalid_config",
+            LazyStreamError::UnknownProfile(_) => "unknown_profile",
+            LazyStreamError::UnknownSetting(..) => "unknown_setting",
+            LazyStreamError::InvalidSetting(_) => "invalid_setting",
+        }
+    }
+}
+
+/// Find the first `LazyStreamError` in the error chain, either directly or as
+/// the context of another error
+pub fn find(e: &Error) -> Option<&LazyStreamError> {
+    e.iter_chain().find_map(|fail| {
+        fail.downcast_ref::<LazyStreamError>().or_else(|| {
+            fail.downcast_ref::<Context<LazyStreamError>>()
+                .map(Context::get_context)
+        })
+    })
+}
+
+/// Exit code for an error, 1 if it isn't a `LazyStreamError`
+pub fn exit_code(e: &Error) -> i32 {
+    find(e).map_or(1, LazyStreamError::exit_code)
+}
@@ -1,22 +1,24 @@
 use crate::{
-    log_error,
-    opt::{Cdn, Command, GenerateCommand, Opt, Quality, Sport},
-    stream::{Game, LazyStream},
+    exit_with_error,
+    league::LeagueProvider,
+    opt::{Cdn, Command, DateRange, GenerateCommand, Opt, Quality, QualityFallback},
+    stream::{Game, LazyStream, MediaState},
+    xmltv::{Airing, Channel, EpisodeNum, Icon, Programme, Tv},
     VERSION,
 };
-use async_std::{fs, process, task};
-use chrono::{Local, Duration};
+use async_std::{fs, task};
+use chrono::{DateTime, Duration, FixedOffset, Local, NaiveDate, TimeZone, Timelike};
 use failure::Error;
 use std::path::PathBuf;
 
-const NHL_ICON: &str = "https://upload.wikimedia.org/wikipedia/en/thumb/3/3a/05_NHL_Shield.svg/1200px-05_NHL_Shield.svg.png";
-const MLB_ICON: &str = "https://upload.wikimedia.org/wikipedia/en/thumb/a/a6/Major_League_Baseball_logo.svg/1200px-Major_League_Baseball_logo.svg.png";
+/// Channels of the XMLTV output on days with fewer streams
+const MIN_CHANNELS: u32 = 100;
 
 pub fn run(opts: Opt) {
+    let error_format = opts.error_format;
     task::block_on(async {
         if let Err(e) = process(opts).await {
-            log_error(&e);
-            process::exit(1);
+            exit_with_error(&e, error_format);
         };
     });
 }
@@ -35,12 +37,20 @@ async fn process(opts: Opt) -> Result<(), Error> {
 
     if let Some(quality) = opts.quality {
         lazy_stream
-            .resolve_with_quality_link(opts.cdn, quality)
+            .resolve_with_quality_link(opts.cdn, quality, opts.quality_fallback)
             .await;
     } else {
         lazy_stream.resolve_with_master_link(opts.cdn).await;
     }
 
+    // Radio broadcasts are only listed in playlists without XMLTV
+    if let Command::Generate {
+        command: GenerateCommand::Playlist { .. },
+    } = &opts.command
+    {
+        lazy_stream.resolve_audio_with_master_link(opts.cdn).await;
+    }
+
     let games = lazy_stream.games();
 
     if let Command::Generate { command } = opts.command {
@@ -56,6 +66,7 @@ async fn process(opts: Opt) -> Result<(), Error> {
                     games.clone(),
                     opts.cdn,
                     opts.quality,
+                    opts.quality_fallback,
                     true,
                     start_channel,
                     Some(&channel_prefix),
@@ -63,11 +74,29 @@ async fn process(opts: Opt) -> Result<(), Error> {
                 .await?;
 
                 let path = path.with_extension("xml");
-                create_xmltv(path, games, start_channel, opts.sport, &channel_prefix).await?;
+                create_xmltv(
+                    path,
+                    games,
+                    start_channel,
+                    lazy_stream.league(),
+                    opts.date_range(),
+                    &channel_prefix,
+                )
+                .await?;
             }
             GenerateCommand::Playlist { file } => {
                 let path = file.with_extension("m3u");
-                create_playlist(path, games, opts.cdn, opts.quality, false, 1000, None).await?;
+                create_playlist(
+                    path,
+                    games,
+                    opts.cdn,
+                    opts.quality,
+                    opts.quality_fallback,
+                    false,
+                    1000,
+                    None,
+                )
+                .await?;
             }
         }
     }
@@ -75,11 +104,13 @@ async fn process(opts: Opt) -> Result<(), Error> {
     Ok(())
 }
 
+#[allow(clippy::too_many_arguments)]
 async fn create_playlist(
     path: PathBuf,
     mut games: Vec<Game>,
     cdn: Cdn,
     quality: Option<Quality>,
+    fallback: QualityFallback,
     is_xmltv: bool,
     start_channel: u32,
     channel_prefix: Option<&str>,
@@ -89,18 +120,31 @@ async fn create_playlist(
 
     let mut id: u32 = 0;
     for game in games.iter_mut() {
-        for (_, stream) in game.streams.as_mut().unwrap().iter_mut() {
+        let game_number_suffix = game.game_number_suffix();
+        for (_, stream) in game.streams.iter_mut().flatten() {
             let link = if let Some(quality) = quality {
-                stream.quality_link(cdn, quality).await
+                stream.quality_link(cdn, quality, fallback).await
             } else {
                 stream.master_link(cdn).await
             };
 
+            if let (Some(quality), Some(rendition)) = (quality, stream.quality_fallback()) {
+                println!(
+                    "{} @ {}{} {}: {} not available, using {}",
+                    game.away_team.team_name,
+                    game.home_team.team_name,
+                    game_number_suffix,
+                    stream.feed_type,
+                    quality,
+                    rendition,
+                );
+            }
+
             let title = if is_xmltv {
                 format!("{} {}", channel_prefix.unwrap(), id + 1)
             } else {
                 format!(
-                    "{} {} @ {} {}",
+                    "{} {} @ {}{} {}",
                     game.game_date
                         .with_timezone(&Local)
                         .time()
@@ -108,7 +152,8 @@ async fn create_playlist(
                         .to_string(),
                     game.away_team.team_name,
                     game.home_team.team_name,
-                    stream.feed_type,
+                    game_number_suffix,
+                    stream.label(),
                 )
             };
             let record = format!(
@@ -125,10 +170,44 @@ async fn create_playlist(
         }
     }
 
-    // Create additional blank records for all 100 channels
+    // Radio broadcasts are listed after the games, XMLTV channels are video only
+    if !is_xmltv {
+        for game in games.iter_mut() {
+            let game_number_suffix = game.game_number_suffix();
+            for stream in game.audio_streams.iter_mut().flatten() {
+                let link = stream.master_link(cdn).await;
+
+                let title = format!(
+                    "{} {} @ {}{} {}",
+                    game.game_date
+                        .with_timezone(&Local)
+                        .time()
+                        .format("%-I:%M %p")
+                        .to_string(),
+                    game.away_team.team_name,
+                    game.home_team.team_name,
+                    game_number_suffix,
+                    stream.label(),
+                );
+                let record = format!(
+                    "#EXTINF:-1 CUID=\"{}\" tvg-id=\"{}\" tvg-name=\"{} {}\" radio=\"true\",{}\n{}\n",
+                    start_channel + id,
+                    start_channel + id,
+                    channel_prefix.unwrap_or("Lazyman"),
+                    id + 1,
+                    title,
+                    link.unwrap_or_else(|_| ".".to_string())
+                );
+                m3u.push_str(&record);
+                id += 1;
+            }
+        }
+    }
+
+    // Create additional blank records for the rest of the channels
     if is_xmltv {
         let _id = id;
-        for _ in _id..100 {
+        for _ in _id..channel_count(&games) {
             let title = format!("{} {}", channel_prefix.unwrap(), id + 1);
             let record = format!(
                 "#EXTINF:-1 CUID=\"{}\" tvg-id=\"{}\" tvg-name=\"{} {}\",{}\n.\n",
@@ -154,98 +233,198 @@ async fn create_xmltv(
     path: PathBuf,
     mut games: Vec<Game>,
     start_channel: u32,
-    sport: Sport,
+    league: &dyn LeagueProvider,
+    date_range: DateRange,
     channel_prefix: &str,
 ) -> Result<(), Error> {
-    let mut xmltv = String::new();
-    xmltv.push_str(&format!(
-        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
-         <!DOCTYPE tv SYSTEM \"xmltv.dd\">\
-         \n\
-         \n  <tv generator-info-name=\"lazystream\" source-info-name=\"lazystream - {}\">",
-        VERSION
-    ));
-
-    let icon = match sport {
-        Sport::Nhl => NHL_ICON,
-        Sport::Mlb => MLB_ICON,
-    };
+    let icon = league.icon();
+    let channels = (0..channel_count(&games))
+        .map(|id| Channel {
+            id: (start_channel + id).to_string(),
+            display_name: format!("{} {}", channel_prefix, id + 1),
+            icon: Some(Icon::new(icon)),
+        })
+        .collect();
 
-    let mut id: u32 = 0;
-    while id < 100 {
-        let record = format!(
-            "\n    <channel id=\"{}\">\
-             \n      <display-name>{} {}</display-name>\
-             \n      <icon src=\"{}\"></icon>\
-             \n    </channel>",
-            start_channel + id,
-            channel_prefix,
-            id + 1,
-            icon
-        );
-        xmltv.push_str(&record);
-        id += 1;
-    }
+    // Every channel is filled from the start of the first day to the end of the last, or
+    // further when a game falls outside of that
+    let now = Local::now();
+    let times: Vec<_> = games
+        .iter()
+        .map(|game| game_times(game, league.game_length(), now))
+        .collect();
+    let guide_start = times
+        .iter()
+        .map(|(start, _)| *start)
+        .chain(std::iter::once(midnight(date_range.start)))
+        .min()
+        .unwrap();
+    let guide_stop = times
+        .iter()
+        .map(|(_, stop)| *stop)
+        .chain(std::iter::once(midnight(date_range.end.succ())))
+        .max()
+        .unwrap();
 
+    let mut programmes = vec![];
     let mut id: u32 = 0;
-    for game in games.iter_mut() {
+    for (game, (start, stop)) in games.iter_mut().zip(times) {
         let icons = if let Some(game_cuts) = game.game_cuts().await {
-            let cuts = vec![&game_cuts.cut_320_180, &game_cuts.cut_2048_1152];
-            let mut icons = String::new();
-            for cut in cuts {
-                let icon = format!(
-                    "\n      <icon src=\"{}\" width=\"{}\" height=\"{}\"></icon>",
-                    cut.src, cut.width, cut.height,
-                );
-                icons.push_str(&icon);
-            }
-            icons
+            vec![&game_cuts.cut_320_180, &game_cuts.cut_2048_1152]
+                .into_iter()
+                .map(|cut| Icon {
+                    src: cut.src.clone(),
+                    width: Some(cut.width),
+                    height: Some(cut.height),
+                })
+                .collect()
         } else {
-            String::from("\n      <icon src=\"\"></icon>")
+            vec![Icon::new(icon)]
         };
 
         let mut description = game.description().await.unwrap_or_else(|| String::from(""));
         if description.is_empty() {
-            description = format!("Watch the {} take on the {}.",
-                                  game.away_team.team_name, game.home_team.team_name);
+            description = format!(
+                "Watch the {} take on the {}.",
+                game.away_team.team_name, game.home_team.team_name
+            );
         }
 
-        for (_, stream) in game.streams.as_mut().unwrap().iter_mut() {
-            let start = game.game_date.with_timezone(&Local);
-            let stop = start + Duration::hours(4);
-            let title = format!(
-                "{} @ {} ({})",
-                game.away_team.team_name,
-                game.home_team.team_name,
-                stream.feed_type
-            );
+        let title = format!(
+            "{} @ {}{}",
+            game.away_team.team_name,
+            game.home_team.team_name,
+            game.game_number_suffix(),
+        );
+        let starts_at = if date_range.is_single_day() {
+            format!("Starts at {}", start.format("%-I:%M %p"))
+        } else {
+            format!(
+                "Starts {} at {}",
+                start.format("%a %b %-d"),
+                start.format("%-I:%M %p")
+            )
+        };
+
+        for (_, stream) in game.streams.iter_mut().flatten() {
+            let channel = (start_channel + id).to_string();
+
+            if guide_start < start {
+                let mut pre_game = Programme::new(&channel, guide_start, start, "Pre-game");
+                pre_game.sub_title = Some(starts_at.clone());
+                pre_game.desc = Some(format!("{} ({})", title, stream.feed_name()));
+                pre_game.categories = vec![String::from("Sports")];
+                programmes.push(pre_game);
+            }
+
+            let mut programme = Programme::new(&channel, start, stop, &title);
+            programme.sub_title = Some(stream.feed_name());
+            programme.desc = Some(description.clone());
+            programme.date = Some(start.date().naive_local());
+            programme.categories = vec![String::from("Sports"), league.category().to_owned()];
+            programme.icons = icons.clone();
+            // Guide apps tell games apart by their air date, or every game of a matchup
+            // is treated as a rerun of the first
+            programme.episode_nums = vec![EpisodeNum {
+                system: String::from("original-air-date"),
+                value: start.format("%Y-%m-%d %H:%M:%S").to_string(),
+            }];
+            programme.airing = match stream.media_state {
+                MediaState::Live | MediaState::Upcoming => Some(Airing::New),
+                MediaState::Archived => Some(Airing::PreviouslyShown),
+                MediaState::Unknown => None,
+            };
+            if stream.media_state == MediaState::Live {
+                programme.categories.push(String::from("Live"));
+            }
+            programmes.push(programme);
+
+            if stop < guide_stop {
+                programmes.push(off_air(&channel, stop, guide_stop));
+            }
 
-            let record = format!(
-                "\n    <programme channel=\"{}\" start=\"{} {}\" stop=\"{} {}\">\
-                     \n      <title lang=\"en\">{}</title>\
-                     \n      <desc lang=\"en\">{}</desc>\
-                     \n      <category lang=\"en\">Sports</category>\
-                     {}\
-                     \n    </programme>",
-                start_channel + id,
-                start.format("%Y%m%d%H%M%S"),
-                start.format("%z"),
-                stop.format("%Y%m%d%H%M%S"),
-                stop.format("%z"),
-                title,
-                description,
-                icons,
-            );
-            xmltv.push_str(&record);
             id += 1;
         }
     }
 
-    xmltv.push_str("\n  </tv>");
+    // Channels without a game are off air the whole time
+    for id in id..channel_count(&games) {
+        let channel = (start_channel + id).to_string();
+        programmes.push(off_air(&channel, guide_start, guide_stop));
+    }
 
-    fs::write(&path, xmltv).await?;
+    let tv = Tv {
+        generator_info_name: String::from("lazystream"),
+        source_info_name: format!("lazystream - {}", VERSION),
+        channels,
+        programmes,
+    };
+
+    fs::write(&path, tv.to_xml()).await?;
 
     println!("Xmltv file saved to: {:?}", path);
 
     Ok(())
 }
+
+/// Number of XMLTV channels. Guides are set up once for a fixed set of channels, so there
+/// are always at least `MIN_CHANNELS`, and more on days with more streams than that
+fn channel_count(games: &[Game]) -> u32 {
+    let streams: usize = games
+        .iter()
+        .map(|game| game.streams.as_ref().map_or(0, |streams| streams.len()))
+        .sum();
+
+    MIN_CHANNELS.max(streams as u32)
+}
+
+/// Start and stop of a game's programme. A live game that runs past its typical length is
+/// extended to the next half hour, so the guide doesn't show it as over
+fn game_times(
+    game: &Game,
+    game_length: Duration,
+    now: DateTime<Local>,
+) -> (DateTime<FixedOffset>, DateTime<FixedOffset>) {
+    let start = game.game_date.with_timezone(&Local);
+    let mut stop = start + game_length;
+
+    if game.is_live() && stop < now + Duration::minutes(15) {
+        stop = next_half_hour(now + Duration::minutes(15));
+    }
+
+    (fixed(start), fixed(stop))
+}
+
+/// Round up to the next half hour, guide grids are laid out in half hours
+fn next_half_hour(time: DateTime<Local>) -> DateTime<Local> {
+    let time = time.with_nanosecond(0).unwrap_or(time);
+    let past = i64::from(time.minute() % 30) * 60 + i64::from(time.second());
+
+    if past == 0 {
+        time
+    } else {
+        time + Duration::seconds(30 * 60 - past)
+    }
+}
+
+/// Local midnight at the start of a day
+fn midnight(date: NaiveDate) -> DateTime<FixedOffset> {
+    let start_of_day = date.and_hms(0, 0, 0);
+    let local = Local
+        .from_local_datetime(&start_of_day)
+        .earliest()
+        .unwrap_or_else(|| Local.from_utc_datetime(&start_of_day));
+
+    fixed(local)
+}
+
+/// Local time with its UTC offset, which is how XMLTV times are written
+fn fixed(time: DateTime<Local>) -> DateTime<FixedOffset> {
+    time.with_timezone(time.offset())
+}
+
+fn off_air(channel: &str, start: DateTime<FixedOffset>, stop: DateTime<FixedOffset>) -> Programme {
+    let mut programme = Programme::new(channel, start, stop, "Off air");
+    programme.desc = Some(String::from("No game is on this channel."));
+    programme
+}
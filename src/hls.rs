@@ -0,0 +1,370 @@
+use failure::{bail, Error};
+use std::collections::HashMap;
+
+/// Parsed HLS master playlist
+#[derive(Debug, Clone, PartialEq)]
+pub struct MasterPlaylist {
+    pub variants: Vec<Variant>,
+    pub media: Vec<Media>,
+}
+
+/// Variant stream from an `#EXT-X-STREAM-INF` tag
+#[derive(Debug, Clone, PartialEq)]
+pub struct Variant {
+    /// Absolute uri of the variant playlist
+    pub uri: String,
+    pub bandwidth: u64,
+    pub average_bandwidth: Option<u64>,
+    pub resolution: Option<Resolution>,
+    pub frame_rate: Option<f64>,
+    pub codecs: Option<String>,
+    /// Group id of the audio renditions for this variant
+    pub audio: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Resolution {
+    pub width: u32,
+    pub height: u32,
+}
+
+/// Rendition from an `#EXT-X-MEDIA` tag
+#[derive(Debug, Clone, PartialEq)]
+pub struct Media {
+    pub media_type: String,
+    pub group_id: String,
+    pub name: String,
+    pub language: Option<String>,
+    pub default: bool,
+    pub autoselect: bool,
+    /// Absolute uri of the rendition playlist, if it isn't muxed into the variant
+    pub uri: Option<String>,
+}
+
+impl MasterPlaylist {
+    /// Parse a master playlist, resolving all uris relative to `base_uri`,
+    /// the link the playlist was fetched from
+    pub fn parse(base_uri: &str, m3u8: &str) -> Result<MasterPlaylist, Error> {
+        let mut lines = m3u8.lines().map(str::trim).filter(|line| !line.is_empty());
+
+        if lines.next() != Some("#EXTM3U") {
+            bail!("Playlist is missing #EXTM3U header");
+        }
+
+        let mut variants = vec![];
+        let mut media = vec![];
+        let mut stream_inf: Option<HashMap<String, String>> = None;
+
+        for line in lines {
+            if let Some(attributes) = line.strip_prefix("#EXT-X-STREAM-INF:") {
+                stream_inf = Some(parse_attributes(attributes));
+            } else if let Some(attributes) = line.strip_prefix("#EXT-X-MEDIA:") {
+                media.push(Media::from_attributes(
+                    base_uri,
+                    parse_attributes(attributes),
+                )?);
+            } else if line.starts_with('#') {
+                continue;
+            } else if let Some(attributes) = stream_inf.take() {
+                variants.push(Variant::from_attributes(base_uri, line, attributes)?);
+            }
+        }
+
+        Ok(MasterPlaylist { variants, media })
+    }
+}
+
+impl Variant {
+    /// Height of the variant, 0 if it has no resolution
+    pub fn height(&self) -> u32 {
+        self.resolution.map_or(0, |resolution| resolution.height)
+    }
+
+    /// Frame rate rounded to a whole number, variants without one are assumed to be 30fps
+    pub fn rounded_frame_rate(&self) -> u32 {
+        self.frame_rate.map_or(30, |rate| rate.round() as u32)
+    }
+
+    /// Short description of the variant, E.g. `720p60 (5.6 Mbps)`
+    pub fn name(&self) -> String {
+        let mbps = self.bandwidth as f64 / 1_000_000.0;
+
+        match (self.height(), self.rounded_frame_rate()) {
+            (0, _) => format!("{:.1} Mbps", mbps),
+            (height, rate) if rate > 30 => format!("{}p{} ({:.1} Mbps)", height, rate, mbps),
+            (height, _) => format!("{}p ({:.1} Mbps)", height, mbps),
+        }
+    }
+
+    fn from_attributes(
+        base_uri: &str,
+        uri: &str,
+        attributes: HashMap<String, String>,
+    ) -> Result<Variant, Error> {
+        let bandwidth = match attributes.get("BANDWIDTH").map(|b| b.parse()) {
+            Some(Ok(bandwidth)) => bandwidth,
+            _ => bail!("#EXT-X-STREAM-INF is missing a valid BANDWIDTH"),
+        };
+
+        Ok(Variant {
+            uri: resolve_uri(base_uri, uri),
+            bandwidth,
+            average_bandwidth: attributes
+                .get("AVERAGE-BANDWIDTH")
+                .and_then(|b| b.parse().ok()),
+            resolution: attributes.get("RESOLUTION").and_then(|r| {
+                let mut parts = r.splitn(2, 'x');
+                Some(Resolution {
+                    width: parts.next()?.parse().ok()?,
+                    height: parts.next()?.parse().ok()?,
+                })
+            }),
+            frame_rate: attributes.get("FRAME-RATE").and_then(|f| f.parse().ok()),
+            codecs: attributes.get("CODECS").cloned(),
+            audio: attributes.get("AUDIO").cloned(),
+        })
+    }
+}
+
+impl Media {
+    fn from_attributes(
+        base_uri: &str,
+        attributes: HashMap<String, String>,
+    ) -> Result<Media, Error> {
+        let (media_type, group_id, name) = match (
+            attributes.get("TYPE"),
+            attributes.get("GROUP-ID"),
+            attributes.get("NAME"),
+        ) {
+            (Some(media_type), Some(group_id), Some(name)) => {
+                (media_type.clone(), group_id.clone(), name.clone())
+            }
+            _ => bail!("#EXT-X-MEDIA is missing TYPE, GROUP-ID or NAME"),
+        };
+
+        Ok(Media {
+            media_type,
+            group_id,
+            name,
+            language: attributes.get("LANGUAGE").cloned(),
+            default: attributes.get("DEFAULT").map(String::as_str) == Some("YES"),
+            autoselect: attributes.get("AUTOSELECT").map(String::as_str) == Some("YES"),
+            uri: attributes.get("URI").map(|uri| resolve_uri(base_uri, uri)),
+        })
+    }
+}
+
+/// Parse an attribute list, E.g. `BANDWIDTH=1200000,CODECS="avc1.4d401f,mp4a.40.2"`.
+/// Quotes are removed from quoted string values
+fn parse_attributes(s: &str) -> HashMap<String, String> {
+    let mut attributes = HashMap::new();
+    let mut chars = s.chars().peekable();
+
+    loop {
+        let name: String = chars
+            .by_ref()
+            .take_while(|c| *c != '=')
+            .collect::<String>()
+            .trim()
+            .to_owned();
+        if name.is_empty() {
+            break;
+        }
+
+        let mut value = String::new();
+        if chars.peek() == Some(&'"') {
+            chars.next();
+            value.extend(chars.by_ref().take_while(|c| *c != '"'));
+            chars.by_ref().take_while(|c| *c != ',').for_each(drop);
+        } else {
+            value.extend(chars.by_ref().take_while(|c| *c != ','));
+        }
+
+        attributes.insert(name, value.trim().to_owned());
+    }
+
+    attributes
+}
+
+/// Resolve a playlist uri against the uri of the playlist it was found in
+pub fn resolve_uri(base_uri: &str, uri: &str) -> String {
+    if uri.contains("://") {
+        return uri.to_owned();
+    }
+
+    let (scheme, rest) = match base_uri.find("://") {
+        Some(idx) => (&base_uri[..idx], &base_uri[idx + 3..]),
+        None => return uri.to_owned(),
+    };
+
+    if uri.starts_with("//") {
+        return format!("{}:{}", scheme, uri);
+    }
+
+    let rest = rest.split(|c| c == '?' || c == '#').next().unwrap_or("");
+    let (authority, path) = match rest.find('/') {
+        Some(idx) => (&rest[..idx], &rest[idx..]),
+        None => (rest, "/"),
+    };
+
+    let joined = if uri.starts_with('/') {
+        uri.to_owned()
+    } else {
+        let dir = &path[..=path.rfind('/').unwrap_or(0)];
+        format!("{}{}", dir, uri)
+    };
+
+    let (joined, query) = match joined.find('?') {
+        Some(idx) => (&joined[..idx], &joined[idx..]),
+        None => (&joined[..], ""),
+    };
+
+    let mut segments: Vec<&str> = vec![];
+    for segment in joined.split('/').skip(1) {
+        match segment {
+            "." => {}
+            ".." => {
+                segments.pop();
+            }
+            _ => segments.push(segment),
+        }
+    }
+
+    format!("{}://{}/{}{}", scheme, authority, segments.join("/"), query)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BASE_URI: &str = "https://hls.example.com/nhl/2020/master.m3u8?token=abc";
+
+    const MASTER: &str = r#"#EXTM3U
+#EXT-X-VERSION:4
+#EXT-X-INDEPENDENT-SEGMENTS
+#EXT-X-MEDIA:TYPE=AUDIO,GROUP-ID="aac",NAME="English",LANGUAGE="en",DEFAULT=YES,AUTOSELECT=YES
+#EXT-X-MEDIA:TYPE=AUDIO,GROUP-ID="aac",NAME="Home Radio, KRLV",LANGUAGE="en",AUTOSELECT=YES,URI="audio/home.m3u8"
+
+#EXT-X-STREAM-INF:BANDWIDTH=6600000,AVERAGE-BANDWIDTH=5600000,RESOLUTION=1280x720,FRAME-RATE=59.94,CODECS="avc1.4d401f,mp4a.40.2",AUDIO="aac"
+720p60/index.m3u8
+#EXT-X-STREAM-INF:BANDWIDTH=1200000,CODECS="avc1.4d401e,mp4a.40.2",RESOLUTION=640x360
+../low/360p.m3u8
+#EXT-X-STREAM-INF:BANDWIDTH=192000,CODECS="mp4a.40.2"
+https://cdn.example.com/audio-only.m3u8
+"#;
+
+    #[test]
+    fn parses_master_playlist() {
+        let playlist = MasterPlaylist::parse(BASE_URI, MASTER).unwrap();
+        assert_eq!(playlist.variants.len(), 3);
+        assert_eq!(playlist.media.len(), 2);
+
+        let variant = &playlist.variants[0];
+        assert_eq!(
+            variant.uri,
+            "https://hls.example.com/nhl/2020/720p60/index.m3u8"
+        );
+        assert_eq!(variant.bandwidth, 6_600_000);
+        assert_eq!(variant.average_bandwidth, Some(5_600_000));
+        assert_eq!(
+            variant.resolution,
+            Some(Resolution {
+                width: 1280,
+                height: 720
+            })
+        );
+        assert_eq!(variant.rounded_frame_rate(), 60);
+        assert_eq!(variant.codecs.as_deref(), Some("avc1.4d401f,mp4a.40.2"));
+        assert_eq!(variant.audio.as_deref(), Some("aac"));
+        assert_eq!(variant.name(), "720p60 (6.6 Mbps)");
+
+        let variant = &playlist.variants[1];
+        assert_eq!(variant.uri, "https://hls.example.com/nhl/low/360p.m3u8");
+        assert_eq!(variant.height(), 360);
+        assert_eq!(variant.name(), "360p (1.2 Mbps)");
+
+        let variant = &playlist.variants[2];
+        assert_eq!(variant.uri, "https://cdn.example.com/audio-only.m3u8");
+        assert_eq!(variant.resolution, None);
+        assert_eq!(variant.name(), "0.2 Mbps");
+    }
+
+    #[test]
+    fn parses_media() {
+        let playlist = MasterPlaylist::parse(BASE_URI, MASTER).unwrap();
+
+        assert_eq!(
+            playlist.media[0],
+            Media {
+                media_type: String::from("AUDIO"),
+                group_id: String::from("aac"),
+                name: String::from("English"),
+                language: Some(String::from("en")),
+                default: true,
+                autoselect: true,
+                uri: None,
+            }
+        );
+
+        let media = &playlist.media[1];
+        assert_eq!(media.name, "Home Radio, KRLV");
+        assert!(!media.default);
+        assert_eq!(
+            media.uri.as_deref(),
+            Some("https://hls.example.com/nhl/2020/audio/home.m3u8")
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_playlists() {
+        assert!(MasterPlaylist::parse(BASE_URI, "#EXT-X-VERSION:4\n").is_err());
+
+        let missing_bandwidth = "#EXTM3U\n#EXT-X-STREAM-INF:RESOLUTION=640x360\n360p.m3u8\n";
+        assert!(MasterPlaylist::parse(BASE_URI, missing_bandwidth).is_err());
+    }
+
+    #[test]
+    fn parses_quoted_attributes() {
+        let attributes =
+            parse_attributes(r#"BANDWIDTH=1200000,CODECS="avc1.4d401f,mp4a.40.2", NAME="A, B""#);
+
+        assert_eq!(attributes.len(), 3);
+        assert_eq!(attributes["BANDWIDTH"], "1200000");
+        assert_eq!(attributes["CODECS"], "avc1.4d401f,mp4a.40.2");
+        assert_eq!(attributes["NAME"], "A, B");
+    }
+
+    #[test]
+    fn resolves_uris() {
+        let base = "https://hls.example.com/a/b/master.m3u8?token=abc";
+
+        assert_eq!(
+            resolve_uri(base, "720p.m3u8"),
+            "https://hls.example.com/a/b/720p.m3u8"
+        );
+        assert_eq!(
+            resolve_uri(base, "./x/../720p.m3u8?v=2"),
+            "https://hls.example.com/a/b/720p.m3u8?v=2"
+        );
+        assert_eq!(
+            resolve_uri(base, "../../720p.m3u8"),
+            "https://hls.example.com/720p.m3u8"
+        );
+        assert_eq!(
+            resolve_uri(base, "/root/720p.m3u8"),
+            "https://hls.example.com/root/720p.m3u8"
+        );
+        assert_eq!(
+            resolve_uri(base, "//cdn.example.com/720p.m3u8"),
+            "https://cdn.example.com/720p.m3u8"
+        );
+        assert_eq!(
+            resolve_uri(base, "http://other.example.com/720p.m3u8"),
+            "http://other.example.com/720p.m3u8"
+        );
+        assert_eq!(
+            resolve_uri("https://hls.example.com", "720p.m3u8"),
+            "https://hls.example.com/720p.m3u8"
+        );
+    }
+}
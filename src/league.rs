@@ -0,0 +1,253 @@
+use crate::{
+    api::model::{GameContentEpgItem, GameContentResponse, Schedule, Team},
+    opt::Sport,
+    stream::StreamKind,
+};
+use chrono::{Duration, NaiveDate};
+use failure::{format_err, Error};
+use futures::future::{FutureExt, LocalBoxFuture};
+use stats_api::{MlbClient, NhlClient};
+use std::sync::Arc;
+
+const NHL_ICON: &str = "https://upload.wikimedia.org/wikipedia/en/thumb/3/3a/05_NHL_Shield.svg/1200px-05_NHL_Shield.svg.png";
+const MLB_ICON: &str = "https://upload.wikimedia.org/wikipedia/en/thumb/a/a6/Major_League_Baseball_logo.svg/1200px-Major_League_Baseball_logo.svg.png";
+
+/// Common nicknames of NHL teams, by team abbreviation
+const NHL_ALIASES: &[(&str, &str)] = &[
+    ("habs", "MTL"),
+    ("sens", "OTT"),
+    ("leafs", "TOR"),
+    ("bolts", "TBL"),
+    ("caps", "WSH"),
+    ("pens", "PIT"),
+    ("canes", "CAR"),
+    ("jackets", "CBJ"),
+    ("hawks", "CHI"),
+    ("avs", "COL"),
+    ("preds", "NSH"),
+    ("nucks", "VAN"),
+    ("knights", "VGK"),
+    ("isles", "NYI"),
+    ("wings", "DET"),
+    ("yotes", "ARI"),
+];
+
+/// Common nicknames of MLB teams, by team abbreviation
+const MLB_ALIASES: &[(&str, &str)] = &[
+    ("yanks", "NYY"),
+    ("bombers", "NYY"),
+    ("bosox", "BOS"),
+    ("chisox", "CWS"),
+    ("dbacks", "ARI"),
+    ("d backs", "ARI"),
+    ("nats", "WSH"),
+    ("cards", "STL"),
+    ("os", "BAL"),
+    ("halos", "LAA"),
+    ("jays", "TOR"),
+    ("bucs", "PIT"),
+    ("buccos", "PIT"),
+    ("friars", "SD"),
+    ("tribe", "CLE"),
+    ("phils", "PHI"),
+    ("stros", "HOU"),
+    ("brew crew", "MIL"),
+    ("as", "OAK"),
+];
+
+/// A league that schedules, teams and game content can be fetched for
+pub trait LeagueProvider: Send + Sync {
+    /// Sport of the league, the league is picked by it and it identifies the league to the
+    /// stream provider and the cache
+    fn sport(&self) -> Sport;
+
+    fn get_schedule_for(&self, date: NaiveDate) -> LocalBoxFuture<'_, Result<Schedule, Error>>;
+
+    fn get_teams(&self) -> LocalBoxFuture<'_, Result<Vec<Team>, Error>>;
+
+    fn get_game_content(
+        &self,
+        game_pk: u64,
+    ) -> LocalBoxFuture<'_, Result<GameContentResponse, Error>>;
+
+    /// Kind of stream the items of an epg section are, `None` if the section isn't playable
+    fn stream_kind(&self, epg_title: &str) -> Option<StreamKind>;
+
+    /// Id the stream provider resolves an epg item's stream with
+    fn stream_id(&self, item: &GameContentEpgItem) -> Option<String>;
+
+    /// Logo of the league, used for XMLTV channels
+    fn icon(&self) -> &'static str;
+
+    /// Category of the league's games in guide apps, used for XMLTV programmes
+    fn category(&self) -> &'static str;
+
+    /// Common nicknames of the league's teams and the abbreviation of the team they're for,
+    /// E.g. `("habs", "MTL")`. Lowercase, as they're matched against normalized input
+    fn team_aliases(&self) -> &'static [(&'static str, &'static str)];
+
+    /// Typical length of a game from its start time, breaks included. XMLTV programmes run
+    /// this long unless the game is still live
+    fn game_length(&self) -> Duration;
+}
+
+/// Every league games can be fetched for
+fn leagues() -> Vec<Arc<dyn LeagueProvider>> {
+    vec![Arc::new(Mlb::default()), Arc::new(Nhl::default())]
+}
+
+/// League of `sport`, looked up in the registry of leagues by their sport
+pub fn for_sport(sport: Sport) -> Result<Arc<dyn LeagueProvider>, Error> {
+    leagues()
+        .into_iter()
+        .find(|league| league.sport() == sport)
+        .ok_or_else(|| format_err!("No league provides {} games", sport))
+}
+
+#[derive(Default)]
+pub struct Mlb {
+    client: MlbClient,
+}
+
+impl LeagueProvider for Mlb {
+    fn sport(&self) -> Sport {
+        Sport::Mlb
+    }
+
+    fn get_schedule_for(&self, date: NaiveDate) -> LocalBoxFuture<'_, Result<Schedule, Error>> {
+        async move {
+            let schedule = self.client.get_schedule_for(date).await?;
+            convert(&schedule)
+        }
+        .boxed_local()
+    }
+
+    fn get_teams(&self) -> LocalBoxFuture<'_, Result<Vec<Team>, Error>> {
+        async move {
+            let teams = self.client.get_all_teams().await?;
+            convert(&teams)
+        }
+        .boxed_local()
+    }
+
+    fn get_game_content(
+        &self,
+        game_pk: u64,
+    ) -> LocalBoxFuture<'_, Result<GameContentResponse, Error>> {
+        async move {
+            let game_content = self.client.get_game_content(game_pk).await?;
+            convert(&game_content)
+        }
+        .boxed_local()
+    }
+
+    fn stream_kind(&self, epg_title: &str) -> Option<StreamKind> {
+        match epg_title {
+            "MLBTV" => Some(StreamKind::Video),
+            "Audio" => Some(StreamKind::Audio),
+            "Condensed Game" => Some(StreamKind::Condensed),
+            "Daily Recap" | "Recap" => Some(StreamKind::Recap),
+            "Extended Highlights" => Some(StreamKind::Highlights),
+            _ => None,
+        }
+    }
+
+    fn stream_id(&self, item: &GameContentEpgItem) -> Option<String> {
+        item.id.map(|id| id.to_string())
+    }
+
+    fn icon(&self) -> &'static str {
+        MLB_ICON
+    }
+
+    fn category(&self) -> &'static str {
+        "Baseball"
+    }
+
+    fn team_aliases(&self) -> &'static [(&'static str, &'static str)] {
+        MLB_ALIASES
+    }
+
+    fn game_length(&self) -> Duration {
+        Duration::minutes(180)
+    }
+}
+
+#[derive(Default)]
+pub struct Nhl {
+    client: NhlClient,
+}
+
+impl LeagueProvider for Nhl {
+    fn sport(&self) -> Sport {
+        Sport::Nhl
+    }
+
+    fn get_schedule_for(&self, date: NaiveDate) -> LocalBoxFuture<'_, Result<Schedule, Error>> {
+        async move {
+            let schedule = self.client.get_schedule_for(date).await?;
+            convert(&schedule)
+        }
+        .boxed_local()
+    }
+
+    fn get_teams(&self) -> LocalBoxFuture<'_, Result<Vec<Team>, Error>> {
+        async move {
+            let teams = self.client.get_teams().await?;
+            convert(&teams)
+        }
+        .boxed_local()
+    }
+
+    fn get_game_content(
+        &self,
+        game_pk: u64,
+    ) -> LocalBoxFuture<'_, Result<GameContentResponse, Error>> {
+        async move {
+            let game_content = self.client.get_game_content(game_pk).await?;
+            convert(&game_content)
+        }
+        .boxed_local()
+    }
+
+    fn stream_kind(&self, epg_title: &str) -> Option<StreamKind> {
+        match epg_title {
+            "NHLTV" => Some(StreamKind::Video),
+            "Audio" => Some(StreamKind::Audio),
+            "Condensed Game" => Some(StreamKind::Condensed),
+            "Recap" => Some(StreamKind::Recap),
+            "Extended Highlights" => Some(StreamKind::Highlights),
+            _ => None,
+        }
+    }
+
+    fn stream_id(&self, item: &GameContentEpgItem) -> Option<String> {
+        item.media_playback_id.clone()
+    }
+
+    fn icon(&self) -> &'static str {
+        NHL_ICON
+    }
+
+    fn category(&self) -> &'static str {
+        "Ice Hockey"
+    }
+
+    fn team_aliases(&self) -> &'static [(&'static str, &'static str)] {
+        NHL_ALIASES
+    }
+
+    fn game_length(&self) -> Duration {
+        Duration::minutes(150)
+    }
+}
+
+/// Convert a stats-api response to our own model, which only keeps what lazystream uses
+fn convert<T, U>(value: &T) -> Result<U, Error>
+where
+    T: serde::Serialize,
+    U: serde::de::DeserializeOwned,
+{
+    let serialized = serde_json::to_vec(value)?;
+    Ok(serde_json::from_slice(&serialized)?)
+}
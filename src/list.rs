@@ -0,0 +1,308 @@
+use crate::{
+    exit_with_error,
+    opt::{Cdn, Command, ListFormat, Opt, Quality, QualityFallback},
+    stream::{Game, LazyStream, Stream, StreamKind},
+};
+use async_std::task;
+use chrono::Local;
+use failure::Error;
+use futures::future;
+use serde::Serialize;
+
+pub fn run(opts: Opt) {
+    task::block_on(async {
+        if let Err(e) = process(&opts).await {
+            exit_with_error(&e, opts.error_format);
+        };
+    });
+}
+
+async fn process(opts: &Opt) -> Result<(), Error> {
+    let (format, resolve) = if let Command::List { format, resolve } = opts.command {
+        (format, resolve)
+    } else {
+        (ListFormat::Table, false)
+    };
+
+    let lazy_stream = LazyStream::new(opts).await?;
+
+    let games = future::join_all(
+        lazy_stream
+            .games()
+            .into_iter()
+            .map(|game| list_game(game, opts, resolve)),
+    )
+    .await;
+
+    let output = match format {
+        ListFormat::Table => format_table(&games),
+        ListFormat::Json => serde_json::to_string_pretty(&games)?,
+        ListFormat::Csv => format_delimited(&games, ',', escape_csv),
+        ListFormat::Tsv => format_delimited(&games, '\t', escape_tsv),
+    };
+    println!("{}", output);
+
+    Ok(())
+}
+
+/// Game as it's listed, with every feed it offers
+#[derive(Serialize)]
+struct ListGame {
+    game_pk: u64,
+    start: String,
+    away: String,
+    away_abbrev: String,
+    home: String,
+    home_abbrev: String,
+    /// Which game of a doubleheader this is, `None` for all other games
+    game_number: Option<u32>,
+    state: String,
+    feeds: Vec<ListFeed>,
+}
+
+#[derive(Serialize)]
+struct ListFeed {
+    kind: String,
+    feed_type: String,
+    name: String,
+    media_state: String,
+    host_link: String,
+    master_link: Option<String>,
+    quality_link: Option<String>,
+    /// Rendition used when the stream doesn't offer the requested quality
+    quality_fallback: Option<String>,
+}
+
+/// Game with its feeds. A game whose content can't be loaded is still listed, without feeds,
+/// so one failing game doesn't fail the whole listing
+async fn list_game(mut game: Game, opts: &Opt, resolve: bool) -> ListGame {
+    let feeds = match list_feeds(&mut game, opts, resolve).await {
+        Ok(feeds) => feeds,
+        Err(e) => {
+            eprintln!(
+                "Couldn't load the feeds of {} @ {}: {}",
+                game.away_team.team_name, game.home_team.team_name, e
+            );
+            vec![]
+        }
+    };
+
+    ListGame {
+        game_pk: game.game_pk,
+        start: game.game_date.with_timezone(&Local).to_rfc3339(),
+        away: game.away_team.name.clone(),
+        away_abbrev: game.away_team.abbreviation.clone(),
+        home: game.home_team.name.clone(),
+        home_abbrev: game.home_team.abbreviation.clone(),
+        game_number: Some(game.game_number).filter(|_| game.doubleheader),
+        state: game
+            .status
+            .as_ref()
+            .map(|status| status.detailed_state.clone())
+            .unwrap_or_default(),
+        feeds,
+    }
+}
+
+async fn list_feeds(game: &mut Game, opts: &Opt, resolve: bool) -> Result<Vec<ListFeed>, Error> {
+    let mut streams: Vec<Stream> = game
+        .streams()
+        .await?
+        .into_iter()
+        .map(|(_, stream)| stream)
+        .collect();
+    streams.append(&mut game.audio_streams().await?);
+    streams.append(&mut game.vod_streams().await?);
+
+    let (cdn, quality, fallback) = (opts.cdn, opts.quality, opts.quality_fallback);
+    let links = if resolve {
+        future::join_all(
+            streams
+                .iter_mut()
+                .map(|stream| resolve_links(stream, cdn, quality, fallback)),
+        )
+        .await
+    } else {
+        streams.iter().map(|_| (None, None)).collect()
+    };
+
+    let feeds = streams
+        .iter()
+        .zip(links)
+        .map(|(stream, (master_link, quality_link))| ListFeed {
+            kind: stream.kind.to_string(),
+            feed_type: stream.feed_type.to_string(),
+            name: stream.feed_name(),
+            media_state: stream.media_state.to_string(),
+            host_link: stream.host_link(cdn),
+            master_link,
+            quality_link,
+            quality_fallback: stream.quality_fallback().map(str::to_owned),
+        })
+        .collect();
+
+    Ok(feeds)
+}
+
+/// Master link of the stream and, for video streams when a quality is specified, the
+/// link of that quality. Links that aren't available yet are `None`
+async fn resolve_links(
+    stream: &mut Stream,
+    cdn: Cdn,
+    quality: Option<Quality>,
+    fallback: QualityFallback,
+) -> (Option<String>, Option<String>) {
+    let master_link = stream.master_link(cdn).await.ok();
+
+    let quality_link = match quality {
+        Some(quality) if master_link.is_some() && stream.kind == StreamKind::Video => {
+            stream.quality_link(cdn, quality, fallback).await.ok()
+        }
+        _ => None,
+    };
+
+    (master_link, quality_link)
+}
+
+const COLUMNS: &[&str] = &[
+    "game_pk",
+    "start",
+    "away",
+    "home",
+    "game_number",
+    "state",
+    "kind",
+    "feed_type",
+    "feed",
+    "media_state",
+    "host_link",
+    "master_link",
+    "quality_link",
+    "quality_fallback",
+];
+
+/// One row per feed, in the order of [`COLUMNS`]. Games without feeds still get a row
+fn rows(games: &[ListGame]) -> Vec<Vec<String>> {
+    let mut rows = vec![];
+
+    for game in games {
+        let game_columns = vec![
+            game.game_pk.to_string(),
+            game.start.clone(),
+            team_column(&game.away_abbrev, &game.away),
+            team_column(&game.home_abbrev, &game.home),
+            game.game_number.map(|n| n.to_string()).unwrap_or_default(),
+            game.state.clone(),
+        ];
+
+        if game.feeds.is_empty() {
+            let mut row = game_columns.clone();
+            row.resize(COLUMNS.len(), String::new());
+            rows.push(row);
+        }
+
+        for feed in &game.feeds {
+            let mut row = game_columns.clone();
+            row.extend(vec![
+                feed.kind.clone(),
+                feed.feed_type.clone(),
+                feed.name.clone(),
+                feed.media_state.clone(),
+                feed.host_link.clone(),
+                feed.master_link.clone().unwrap_or_default(),
+                feed.quality_link.clone().unwrap_or_default(),
+                feed.quality_fallback.clone().unwrap_or_default(),
+            ]);
+            rows.push(row);
+        }
+    }
+
+    rows
+}
+
+/// Abbreviation of a team, or its name for teams without one, E.g. All-Star teams
+fn team_column(abbrev: &str, name: &str) -> String {
+    if abbrev.is_empty() {
+        name.to_owned()
+    } else {
+        abbrev.to_owned()
+    }
+}
+
+/// Columns padded to line up, links and quality fallbacks are left out unless they were
+/// resolved
+fn format_table(games: &[ListGame]) -> String {
+    let rows = rows(games);
+
+    let is_resolved = |column: &str| {
+        (column.ends_with("_link") && column != "host_link") || column == "quality_fallback"
+    };
+    let columns: Vec<_> = (0..COLUMNS.len())
+        .filter(|idx| !is_resolved(COLUMNS[*idx]) || rows.iter().any(|row| !row[*idx].is_empty()))
+        .collect();
+
+    let widths: Vec<_> = columns
+        .iter()
+        .map(|idx| {
+            rows.iter()
+                .map(|row| row[*idx].chars().count())
+                .chain(std::iter::once(COLUMNS[*idx].len()))
+                .max()
+                .unwrap_or(0)
+        })
+        .collect();
+
+    let header: Vec<_> = columns
+        .iter()
+        .map(|idx| COLUMNS[*idx].to_uppercase())
+        .collect();
+
+    std::iter::once(header)
+        .chain(rows.into_iter().map(|row| {
+            columns
+                .iter()
+                .map(|idx| row[*idx].clone())
+                .collect::<Vec<_>>()
+        }))
+        .map(|row| {
+            row.iter()
+                .zip(&widths)
+                .map(|(value, width)| format!("{:width$}", value, width = *width))
+                .collect::<Vec<_>>()
+                .join("  ")
+                .trim_end()
+                .to_owned()
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Header and rows with values separated by `delimiter`
+fn format_delimited(games: &[ListGame], delimiter: char, escape: fn(&str) -> String) -> String {
+    let header: Vec<_> = COLUMNS.iter().map(|column| column.to_string()).collect();
+
+    std::iter::once(header)
+        .chain(rows(games))
+        .map(|row| {
+            row.iter()
+                .map(|value| escape(value))
+                .collect::<Vec<_>>()
+                .join(&delimiter.to_string())
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Quote values containing a delimiter, quote or line break, see RFC 4180
+fn escape_csv(value: &str) -> String {
+    if value.contains(|c: char| c == ',' || c == '"' || c == '\n' || c == '\r') {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_owned()
+    }
+}
+
+/// TSV can't quote values, so tabs and line breaks are replaced with spaces
+fn escape_tsv(value: &str) -> String {
+    value.replace(|c: char| c == '\t' || c == '\n' || c == '\r', " ")
+}
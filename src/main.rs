@@ -1,14 +1,25 @@
-use crate::opt::OutputType;
+use crate::opt::{ErrorFormat, OutputType};
 use colored::Colorize;
 use failure::Error;
 
 mod api;
+mod browse;
+mod cache;
 mod completions;
+mod config;
+mod error;
 mod generate;
+mod hls;
+mod league;
+mod list;
+mod net;
 mod opt;
+mod provider;
 mod select;
 mod stream;
 mod streamlink;
+mod team;
+mod xmltv;
 
 const VERSION: &str = env!("CARGO_PKG_VERSION");
 const HOST: &str = "http://freegamez.ga";
@@ -24,10 +35,13 @@ fn main() {
 
     match output_type {
         OutputType::Select(opts) => crate::select::run(opts),
+        OutputType::List(opts) => crate::list::run(opts),
+        OutputType::Browse(opts) => crate::browse::run(opts),
         OutputType::Generate(opts) => crate::generate::run(opts),
         OutputType::Play(opts) => crate::streamlink::run(opts),
         OutputType::Record(opts) => crate::streamlink::run(opts),
         OutputType::Cast(opts) => crate::streamlink::run(opts),
+        OutputType::Config(opts) => crate::config::run(opts),
         OutputType::Completions(opts) => crate::completions::run(opts),
     }
 }
@@ -41,3 +55,23 @@ pub fn log_error(e: &Error) {
         eprintln!("\n{} {}", caused_colored, cause);
     }
 }
+
+/// Log the error in the specified format, then exit with the exit code of the error
+pub fn exit_with_error(e: &Error, format: ErrorFormat) -> ! {
+    let exit_code = crate::error::exit_code(e);
+
+    match format {
+        ErrorFormat::Text => log_error(e),
+        ErrorFormat::Json => {
+            let json = serde_json::json!({
+                "error": e.to_string(),
+                "kind": crate::error::find(e).map_or("unknown", |e| e.kind()),
+                "exit_code": exit_code,
+                "causes": e.iter_causes().map(|cause| cause.to_string()).collect::<Vec<_>>(),
+            });
+            eprintln!("{}", json);
+        }
+    }
+
+    std::process::exit(exit_code);
+}
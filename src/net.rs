@@ -0,0 +1,144 @@
+use crate::error::LazyStreamError;
+use async_std::{future, task};
+use failure::{Error, Fail, ResultExt};
+use futures::{AsyncReadExt, Future};
+use http_client::{native::NativeClient, Body, HttpClient};
+use std::{
+    collections::hash_map::RandomState,
+    hash::{BuildHasher, Hasher},
+    time::Duration,
+};
+
+const BACKOFF_BASE: Duration = Duration::from_millis(500);
+const BACKOFF_MAX: Duration = Duration::from_secs(8);
+
+/// HTTP context shared by everything that makes requests, so connections are
+/// reused and all requests get the same timeout and retry behaviour
+pub struct HttpContext {
+    client: NativeClient,
+    timeout: Duration,
+    retries: u32,
+}
+
+impl HttpContext {
+    pub fn new(timeout: Duration, retries: u32) -> Self {
+        HttpContext {
+            client: NativeClient::default(),
+            timeout,
+            retries,
+        }
+    }
+
+    /// GET `url` and return the response body as text
+    pub async fn get_text(&self, url: &str) -> Result<String, Error> {
+        self.retry(|| self.get_text_once(url)).await
+    }
+
+    /// Run `f` with a timeout, retrying with exponential backoff and jitter if it fails in a
+    /// way that might not happen again, see [`is_transient`]
+    pub async fn retry<T, F, Fut>(&self, mut f: F) -> Result<T, Error>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T, Error>>,
+    {
+        let mut attempt = 0;
+        loop {
+            let result = match future::timeout(self.timeout, f()).await {
+                Ok(result) => result,
+                Err(_) => Err(RequestError::Timeout(self.timeout.as_secs()).into()),
+            };
+
+            match result {
+                Err(ref e) if attempt < self.retries && is_transient(e) => {
+                    task::sleep(backoff(attempt)).await;
+                    attempt += 1;
+                }
+                Err(e) if is_transient(&e) => {
+                    return Err(e.context(LazyStreamError::Network).into())
+                }
+                // Other errors, E.g. a 404 or a response that can't be parsed, aren't network
+                // failures
+                result => return result,
+            }
+        }
+    }
+
+    async fn get_text_once(&self, url: &str) -> Result<String, Error> {
+        let uri = url.parse::<http::Uri>().context("Failed to build URI")?;
+        let request = http::Request::builder()
+            .method("GET")
+            .uri(uri)
+            .body(Body::empty())
+            .unwrap();
+
+        let resp = self
+            .client
+            .send(request)
+            .await
+            .map_err(|e| RequestError::Connection(e.to_string()))?;
+        if !resp.status().is_success() {
+            return Err(RequestError::Status(resp.status()).into());
+        }
+
+        let mut body = resp.into_body();
+        let mut body_text = String::new();
+        body.read_to_string(&mut body_text)
+            .await
+            .map_err(|e| RequestError::Connection(e.to_string()))
+            .context("Failed to read response body text")?;
+
+        Ok(body_text)
+    }
+}
+
+/// Failure of a single request
+#[derive(Debug, Fail)]
+enum RequestError {
+    #[fail(display = "Request timed out after {}s", _0)]
+    Timeout(u64),
+    #[fail(display = "Connection failed: {}", _0)]
+    Connection(String),
+    #[fail(display = "Request failed with status {}", _0)]
+    Status(http::StatusCode),
+}
+
+/// Whether a failed request might succeed if it's tried again. Timeouts, connection errors
+/// and server errors might, anything else, E.g. a 404 or a bad URL, fails the same way again
+fn is_transient(e: &Error) -> bool {
+    e.iter_chain().any(|cause| {
+        if let Some(e) = cause.downcast_ref::<RequestError>() {
+            match e {
+                RequestError::Status(status) => status.is_server_error(),
+                RequestError::Timeout(_) | RequestError::Connection(_) => true,
+            }
+        } else if let Some(e) = cause.downcast_ref::<curl::Error>() {
+            // Requests of the stats API are made with curl
+            e.is_couldnt_connect()
+                || e.is_couldnt_resolve_host()
+                || e.is_couldnt_resolve_proxy()
+                || e.is_operation_timedout()
+                || e.is_send_error()
+                || e.is_recv_error()
+                || e.is_got_nothing()
+                || e.is_partial_file()
+                || e.is_ssl_connect_error()
+        } else {
+            cause.downcast_ref::<std::io::Error>().is_some()
+        }
+    })
+}
+
+/// Exponential backoff for a retry attempt, with up to 50% jitter added
+fn backoff(attempt: u32) -> Duration {
+    let delay = (BACKOFF_BASE * 2u32.saturating_pow(attempt)).min(BACKOFF_MAX);
+
+    delay + jitter(delay / 2)
+}
+
+/// Pseudo random duration between zero and `max`
+pub fn jitter(max: Duration) -> Duration {
+    // Hashers from a new `RandomState` are randomly keyed, good enough for jitter
+    let random = RandomState::new().build_hasher().finish();
+
+    max * (random % 1000) as u32 / 1000
+}
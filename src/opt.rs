@@ -1,23 +1,75 @@
-use crate::VERSION;
-use chrono::{format::ParseError, NaiveDate};
+use crate::{
+    cache::{Cache, CacheMode},
+    config::Config,
+    error::LazyStreamError,
+    exit_with_error,
+    hls::Variant,
+    league::LeagueProvider,
+    net::HttpContext,
+    provider::{HostTemplate, Lazyman, StreamProvider, DEFAULT_HOST_TEMPLATE},
+    HOST, VERSION,
+};
+use chrono::{Datelike, Local, NaiveDate, NaiveTime, Weekday};
 use failure::{bail, Error};
 use http::Uri;
-use std::{path::PathBuf, str::FromStr};
-use structopt::{clap::AppSettings::DeriveDisplayOrder, StructOpt};
+use std::{cmp::Ordering, path::PathBuf, str::FromStr, sync::Arc, time::Duration};
+use structopt::{
+    clap::{self, AppSettings::DeriveDisplayOrder, ArgMatches},
+    StructOpt,
+};
 
 pub fn parse_opts() -> OutputType {
-    let opts = Opt::from_args();
+    let matches = Opt::clap().get_matches();
+    let mut opts = Opt::from_clap(&matches);
+
+    // The config command reads the file itself, leniently, so it can be used to fix it
+    if !matches!(opts.command, Command::Config { .. }) {
+        if let Err(e) = apply_config(&mut opts, &matches) {
+            exit_with_error(&e, opts.error_format);
+        }
+    }
+
+    // Required unless the config file sets it, so it's only checked once that's applied
+    match &opts.command {
+        Command::Record {
+            command: RecordCommand::Select { output: None, .. },
+        }
+        | Command::Record {
+            command: RecordCommand::Team { output: None, .. },
+        } => clap::Error::with_description(
+            "The following required arguments were not provided:\n    <OUTPUT_DIR>",
+            clap::ErrorKind::MissingRequiredArgument,
+        )
+        .exit(),
+        _ => {}
+    }
 
     match opts.command {
         Command::Select { .. } => OutputType::Select(opts),
+        Command::List { .. } => OutputType::List(opts),
+        Command::Browse { .. } => OutputType::Browse(opts),
         Command::Generate { .. } => OutputType::Generate(opts),
         Command::Play { .. } => OutputType::Play(opts),
         Command::Record { .. } => OutputType::Record(opts),
         Command::Cast { .. } => OutputType::Cast(opts),
+        Command::Config { .. } => OutputType::Config(opts),
         Command::Completions { .. } => OutputType::Completions(opts),
     }
 }
 
+/// Merge the settings of the config file and the profile into the options
+fn apply_config(opts: &mut Opt, matches: &ArgMatches) -> Result<(), Error> {
+    let config = Config::load()?;
+
+    if let Some(profile) = &opts.profile {
+        if !config.has_profile(profile) {
+            return Err(LazyStreamError::UnknownProfile(profile.clone()).into());
+        }
+    }
+
+    config.apply(opts, matches)
+}
+
 #[derive(StructOpt, Debug, Clone)]
 #[structopt(
     name = "lazystream",
@@ -29,28 +81,267 @@ pub fn parse_opts() -> OutputType {
 pub struct Opt {
     #[structopt(subcommand)]
     pub command: Command,
-    #[structopt(long, parse(try_from_str), default_value = Sport::Nhl.into(), global = true, possible_values(&["mlb","nhl"]))]
+    #[structopt(long, parse(try_from_str), default_value = Sport::Nhl.into(), env = "LAZYSTREAM_SPORT", global = true, possible_values(&["mlb","nhl"]))]
     /// Specify which sport to get streams for
     pub sport: Sport,
-    #[structopt(long, parse(try_from_str = parse_date), value_name = "YYYYMMDD", global = true)]
+    #[structopt(long, parse(try_from_str), value_name = "DATE", global = true)]
     /// Specify what date to use for games, defaults to today
-    pub date: Option<NaiveDate>,
-    #[structopt(long, parse(try_from_str), default_value = Cdn::Akc.into(), global = true, possible_values(&["akc","l3c"]))]
+    ///
+    /// Can be a date E.g. '20191208' or '2019-12-08', 'today', 'tomorrow', 'yesterday', a number
+    /// of days from today E.g. '+3' or the next weekday E.g. 'sat'. Ranges of up to 31 days
+    /// can be given as 'START..END' E.g. '2019-12-01..2019-12-07' or 'yesterday..today'
+    pub date: Option<DateRange>,
+    #[structopt(long, parse(try_from_str), default_value = Cdn::Auto.into(), env = "LAZYSTREAM_CDN", global = true, possible_values(&["auto","akc","l3c"]))]
     /// Specify which CDN to use
+    ///
+    /// 'auto' probes every CDN and uses the fastest one. If a CDN fails to resolve or play a
+    /// stream, the next CDN is used
     pub cdn: Cdn,
-    #[structopt(long, parse(try_from_str), global = true, possible_values(&["720p60","720p","540p","504p","360p","288p","224p","216p"]))]
+    #[structopt(long, parse(try_from_str), env = "LAZYSTREAM_QUALITY", global = true)]
     /// Specify a quality to use, otherwise stream will be adaptive
+    ///
+    /// Qualities are matched against the renditions the stream offers. Can be 'best', 'worst',
+    /// a resolution E.g. '720p60' or '540p', a maximum resolution E.g. '<=540p', a maximum
+    /// bitrate E.g. 'max-bitrate=3M' or a frame rate E.g. '60fps'
     pub quality: Option<Quality>,
+    #[structopt(long, parse(try_from_str), default_value = QualityFallback::Lower.into(), env = "LAZYSTREAM_QUALITY_FALLBACK", global = true, possible_values(&["lower","higher","adaptive","none"]))]
+    /// Specify what to use when a stream doesn't offer the specified quality
+    ///
+    /// 'lower' and 'higher' use the nearest rendition below / above the specified quality,
+    /// falling back to the adaptive stream if there is none. 'adaptive' uses the adaptive
+    /// stream and 'none' fails instead
+    pub quality_fallback: QualityFallback,
+    #[structopt(long, value_name = "URL", default_value = HOST, env = "LAZYSTREAM_HOST", global = true)]
+    /// Specify the host used to resolve stream links, such as a local mirror
+    pub host: String,
+    #[structopt(long, value_name = "TEMPLATE", parse(try_from_str), default_value = DEFAULT_HOST_TEMPLATE, env = "LAZYSTREAM_HOST_TEMPLATE", global = true)]
+    /// Specify the link requested from the host to resolve a stream link
+    ///
+    /// {host}, {league}, {date}, {id} and {cdn} are replaced with the host, the league, the
+    /// date of the game E.g. '2019-12-08', the id of the stream and the CDN. Must contain {id}
+    pub host_template: HostTemplate,
+    #[structopt(
+        long,
+        value_name = "SECONDS",
+        default_value = "15",
+        env = "LAZYSTREAM_TIMEOUT",
+        global = true
+    )]
+    /// Specify the timeout for each HTTP request
+    pub timeout: u64,
+    #[structopt(long, default_value = "3", env = "LAZYSTREAM_RETRIES", global = true)]
+    /// Specify how many times an HTTP request that timed out, couldn't connect or got a server
+    /// error is retried
+    pub retries: u32,
+    #[structopt(long, global = true)]
+    /// Ignore cached teams, schedules and game content and fetch them again
+    pub refresh: bool,
+    #[structopt(long, global = true, conflicts_with = "refresh")]
+    /// Only use cached teams, schedules and game content, even if they've expired.
+    /// Stream links are still resolved online
+    pub offline: bool,
+    #[structopt(long, parse(try_from_str), default_value = ErrorFormat::Text.into(), global = true, possible_values(&["text","json"]))]
+    /// Specify how errors are written to stderr
+    pub error_format: ErrorFormat,
+    #[structopt(
+        long,
+        value_name = "PROFILE",
+        env = "LAZYSTREAM_PROFILE",
+        global = true
+    )]
+    /// Use the settings of a profile from the config file, E.g. 'living-room'
+    pub profile: Option<String>,
+    #[structopt(flatten)]
+    pub filter: GameFilter,
+}
+
+/// Filters games are matched against before they're listed or picked
+#[derive(StructOpt, Debug, Clone, Default)]
+pub struct GameFilter {
+    #[structopt(long = "game-type", parse(try_from_str), use_delimiter = true, global = true, possible_values(&["preseason","regular","playoffs","allstar","exhibition"]))]
+    /// Only include games of these types, E.g. 'playoffs' or 'regular,playoffs'
+    pub game_types: Vec<GameType>,
+    #[structopt(long, global = true)]
+    /// Only include games of this season, E.g. '20192020' for NHL or '2019' for MLB
+    pub season: Option<String>,
+    #[structopt(long, value_name = "TEAM", use_delimiter = true, global = true)]
+    /// Only include games these teams play in, E.g. 'VGK,BOS' or 'vegas,bruins'
+    pub teams: Vec<String>,
+    #[structopt(long, value_name = "HH:MM", parse(try_from_str = parse_time), global = true)]
+    /// Only include games starting at or after this local time
+    pub start_after: Option<NaiveTime>,
+    #[structopt(long, value_name = "HH:MM", parse(try_from_str = parse_time), global = true)]
+    /// Only include games starting before this local time. If it's earlier than
+    /// --start-after, the window wraps past midnight
+    pub start_before: Option<NaiveTime>,
+    #[structopt(long, global = true)]
+    /// Only include games that are live
+    pub live: bool,
+    #[structopt(long, global = true, conflicts_with = "live")]
+    /// Only include games that haven't started yet
+    pub upcoming: bool,
+}
+
+fn parse_time(src: &str) -> Result<NaiveTime, Error> {
+    match NaiveTime::parse_from_str(src.trim(), "%H:%M") {
+        Ok(time) => Ok(time),
+        Err(_) => bail!("Time must be supplied as HH:MM E.g. 19:00"),
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GameType {
+    Preseason,
+    Regular,
+    Playoffs,
+    AllStar,
+    Exhibition,
+}
+
+impl GameType {
+    /// Game type of a schedule `gameType` code. MLB splits the postseason into rounds
+    pub fn from_code(code: &str) -> Option<GameType> {
+        match code {
+            "PR" | "S" => Some(GameType::Preseason),
+            "R" => Some(GameType::Regular),
+            "P" | "F" | "D" | "L" | "W" => Some(GameType::Playoffs),
+            "A" => Some(GameType::AllStar),
+            "E" => Some(GameType::Exhibition),
+            _ => None,
+        }
+    }
+}
+
+impl From<GameType> for &str {
+    fn from(game_type: GameType) -> &'static str {
+        match game_type {
+            GameType::Preseason => "preseason",
+            GameType::Regular => "regular",
+            GameType::Playoffs => "playoffs",
+            GameType::AllStar => "allstar",
+            GameType::Exhibition => "exhibition",
+        }
+    }
+}
+
+impl FromStr for GameType {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<GameType, Error> {
+        match s {
+            "preseason" => Ok(GameType::Preseason),
+            "regular" => Ok(GameType::Regular),
+            "playoffs" => Ok(GameType::Playoffs),
+            "allstar" => Ok(GameType::AllStar),
+            "exhibition" => Ok(GameType::Exhibition),
+            _ => bail!(
+                "Option must match 'preseason', 'regular', 'playoffs', 'allstar' or 'exhibition'"
+            ),
+        }
+    }
+}
+
+impl std::fmt::Display for GameType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s: &str = (*self).into();
+        write!(f, "{}", s)
+    }
+}
+
+impl Opt {
+    /// Days to get games for, today if no date was specified
+    pub fn date_range(&self) -> DateRange {
+        self.date.unwrap_or_else(DateRange::today)
+    }
+
+    /// League of the selected sport, games are fetched from it
+    pub fn league_provider(&self) -> Result<Arc<dyn LeagueProvider>, Error> {
+        crate::league::for_sport(self.sport)
+    }
+
+    /// Cache of API responses
+    pub fn cache(&self) -> Cache {
+        let mode = if self.offline {
+            CacheMode::Offline
+        } else if self.refresh {
+            CacheMode::Refresh
+        } else {
+            CacheMode::Normal
+        };
+
+        Cache::new(mode)
+    }
+
+    /// Stream provider used to resolve master links
+    pub fn stream_provider(&self) -> Arc<dyn StreamProvider> {
+        Arc::new(Lazyman::new(&self.host, &self.host_template))
+    }
+
+    /// HTTP context shared by all requests
+    pub fn http_context(&self) -> Arc<HttpContext> {
+        Arc::new(HttpContext::new(
+            Duration::from_secs(self.timeout),
+            self.retries,
+        ))
+    }
 }
 
 #[derive(StructOpt, Debug, PartialEq, Clone)]
 pub enum Command {
-    #[structopt(usage = "lazystream select [--resolve] [OPTIONS]")]
+    #[structopt(
+        usage = "lazystream select [--resolve --game <N> --match <TEXT> --feed <FEED>] [OPTIONS]"
+    )]
     /// Select stream link via command line
     Select {
         #[structopt(long)]
         /// Resolve url to the actual hls link, if it's available
         resolve: bool,
+        #[structopt(flatten)]
+        selection: Selection,
+    },
+    #[structopt(usage = "lazystream list [--format <format>] [--resolve] [OPTIONS]")]
+    /// List games and their feeds without prompting
+    ///
+    /// Prints one row per feed with the game, its state and the host link, in a format
+    /// scripts can read. Use --resolve to also get the master link and, if --quality is
+    /// specified, the quality link of each feed
+    List {
+        #[structopt(long, parse(try_from_str), default_value = ListFormat::Table.into(), possible_values(&["table","json","csv","tsv"]))]
+        /// Specify the output format
+        format: ListFormat,
+        #[structopt(long)]
+        /// Resolve the master and quality links of each feed, if they're available
+        resolve: bool,
+    },
+    #[structopt(
+        usage = "lazystream browse [--output <OUTPUT_DIR> --cast-host <CHROMECAST_HOST> --custom-player <PATH>] [OPTIONS]"
+    )]
+    /// Browse games and feeds in a full-screen terminal UI
+    ///
+    /// Move between games and feeds with the arrow keys and search teams with '/'. The
+    /// selected feed is played with 'p' / Enter, recorded with 'r', cast with 'c' and its
+    /// link copied with 'y'. Playing, recording and casting require Streamlink
+    Browse {
+        #[structopt(
+            long,
+            value_name = "OUTPUT_DIR",
+            parse(from_os_str),
+            default_value = ".",
+            env = "LAZYSTREAM_OUTPUT_DIR"
+        )]
+        /// Directory to save games recorded from the browser
+        output: PathBuf,
+        #[structopt(long, value_name = "CHROMECAST_HOST", env = "LAZYSTREAM_CAST_HOST")]
+        /// IP / Hostname of the Chromecast to cast to. Will search the LAN for one if not specified
+        cast_host: Option<String>,
+        #[structopt(
+            long,
+            name = "PATH",
+            parse(from_os_str),
+            env = "LAZYSTREAM_CUSTOM_PLAYER"
+        )]
+        /// Path to custom player supported by Streamlink (VLC, mpv & more)
+        custom_player: Option<PathBuf>,
     },
     #[structopt(usage = "lazystream generate <SUBCOMMAND> [OPTIONS]", setting = DeriveDisplayOrder)]
     /// Generate an xmltv and/or playlist formatted output for all games
@@ -86,6 +377,17 @@ pub enum Command {
         #[structopt(subcommand)]
         command: CastCommand,
     },
+    #[structopt(usage = "lazystream config <SUBCOMMAND> [--profile <PROFILE>]", setting = DeriveDisplayOrder)]
+    /// Show and edit the settings of the config file
+    ///
+    /// Settings are read from config.toml in the user's config directory E.g.
+    /// ~/.config/lazystream/config.toml, and the profile chosen with --profile. Flags take
+    /// precedence over LAZYSTREAM_* environment variables, which take precedence over the
+    /// profile and then the rest of the config file
+    Config {
+        #[structopt(subcommand)]
+        command: ConfigCommand,
+    },
     #[structopt(usage = "lazystream completions <SHELL> <TARGET_DIR>")]
     /// Output shell completions to a target directory
     Completions {
@@ -98,17 +400,36 @@ pub enum Command {
     },
 }
 
+/// Game and feed to pick in `select`, so it can run without prompting. Anything that
+/// isn't specified is still prompted for
+#[derive(StructOpt, Debug, PartialEq, Clone, Default)]
+pub struct Selection {
+    #[structopt(long, value_name = "N")]
+    /// Pick the game with this number in the list instead of prompting
+    pub game: Option<usize>,
+    #[structopt(long = "match", value_name = "TEXT", conflicts_with = "game")]
+    /// Pick the game a team of which matches TEXT instead of prompting, E.g. 'BOS' or 'Bruins'.
+    /// Fails if more than one game matches
+    pub game_match: Option<String>,
+    #[structopt(long, value_name = "FEED")]
+    /// Pick the feed instead of prompting. Can be the number of the feed in the list, a feed
+    /// type E.g. 'HOME' or a feed name E.g. 'HOME - SN', 'AWAY Radio' or 'Condensed Game'
+    pub feed: Option<String>,
+}
+
 #[derive(StructOpt, Debug, PartialEq, Clone)]
 pub enum PlayCommand {
     #[structopt(
-        usage = "lazystream play select [--restart --proxy <PROXY> --passthrough] [OPTIONS]"
+        usage = "lazystream play select [--restart --game <N> --match <TEXT> --feed <FEED> --proxy <PROXY> --passthrough] [OPTIONS]"
     )]
     /// Select a game from the command line to play in VLC (or --custom-player <PATH>)
     Select {
+        #[structopt(flatten)]
+        selection: Selection,
         #[structopt(long)]
         /// If live, restart the stream from the beginning
         restart: bool,
-        #[structopt(long, parse(try_from_str))]
+        #[structopt(long, parse(try_from_str), env = "LAZYSTREAM_PROXY")]
         /// Proxy server address to be passed to Streamlink
         proxy: Option<Uri>,
         #[structopt(long)]
@@ -117,16 +438,21 @@ pub enum PlayCommand {
         #[structopt(long, value_name = "[HH:]MM:SS", parse(try_from_str = parse_offset))]
         /// Amount of time to skip from the beginning of the stream. For live streams, this is a negative offset from the end of the stream (rewind).
         offset: Option<String>,
-        #[structopt(long, name = "PATH", parse(from_os_str))]
+        #[structopt(
+            long,
+            name = "PATH",
+            parse(from_os_str),
+            env = "LAZYSTREAM_CUSTOM_PLAYER"
+        )]
         /// Path to custom player supported by Streamlink (VLC, mpv & more)
         ///
         /// See https://streamlink.github.io/players.html for list of supported players
         custom_player: Option<PathBuf>,
     },
     #[structopt(
-        usage = "lazystream play team <TEAM> [--restart --feed-type <feed-type> --proxy <PROXY> --passthrough] [OPTIONS]"
+        usage = "lazystream play team <TEAM> [--restart --feed-type <feed-type> --radio --condensed --game-number <N> --doubleheader --proxy <PROXY> --passthrough] [OPTIONS]"
     )]
-    /// Specify team abbreviation. If / when stream is available, will play in VLC (or --custom-player <PATH>)
+    /// Specify a team. If / when stream is available, will play in VLC (or --custom-player <PATH>)
     ///
     /// Example: 'lazystream play team VGK' will play the stream for the
     /// Golden Knights game in VLC.
@@ -136,16 +462,34 @@ pub enum PlayCommand {
     /// will pass that stream to VLC (or --custom-player <PATH>) to play.
     Team {
         #[structopt(name = "TEAM")]
-        /// Team abbreviation
+        /// Team abbreviation, name, city or nickname E.g. VGK, "golden knights" or vegas
         team_abbrev: String,
         #[structopt(long)]
         /// If live, restart the stream from the beginning and record the entire thing
         restart: bool,
-        #[structopt(long, parse(try_from_str), possible_values(&["HOME", "AWAY", "FRENCH", "COMPOSITE", "NATIONAL"]))]
-        /// Specify the feed type to download. Will default to supplied
-        /// team's applicable Home / Away feed
-        feed_type: Option<FeedType>,
         #[structopt(long, parse(try_from_str))]
+        /// Specify the feed type to download E.g. HOME, AWAY, NATIONAL or IN_MARKET_HOME.
+        /// Must be one of the feeds the game offers. Will default to supplied team's applicable Home / Away feed
+        feed_type: Option<FeedType>,
+        #[structopt(long)]
+        /// Play the radio broadcast instead of the video stream
+        radio: bool,
+        #[structopt(long, conflicts_with_all(&["radio", "recap", "highlights", "feed-type"]))]
+        /// Play the condensed game instead of the broadcast. Fails if it hasn't been published yet
+        condensed: bool,
+        #[structopt(long, conflicts_with_all(&["radio", "highlights", "feed-type"]))]
+        /// Play the game recap instead of the broadcast. Fails if it hasn't been published yet
+        recap: bool,
+        #[structopt(long, conflicts_with_all(&["radio", "feed-type"]))]
+        /// Play the extended highlights instead of the broadcast. Fails if they haven't been published yet
+        highlights: bool,
+        #[structopt(long, value_name = "N")]
+        /// Which game of a doubleheader to play E.g. 2. Defaults to the game that's on now or next
+        game_number: Option<u32>,
+        #[structopt(long, conflicts_with = "game-number")]
+        /// Play both games of a doubleheader back to back
+        doubleheader: bool,
+        #[structopt(long, parse(try_from_str), env = "LAZYSTREAM_PROXY")]
         /// Proxy server address to be passed to Streamlink
         proxy: Option<Uri>,
         #[structopt(long)]
@@ -154,7 +498,12 @@ pub enum PlayCommand {
         #[structopt(long, value_name = "[HH:]MM:SS", parse(try_from_str = parse_offset))]
         /// Amount of time to skip from the beginning of the stream. For live streams, this is a negative offset from the end of the stream (rewind).
         offset: Option<String>,
-        #[structopt(long, name = "PATH", parse(from_os_str))]
+        #[structopt(
+            long,
+            name = "PATH",
+            parse(from_os_str),
+            env = "LAZYSTREAM_CUSTOM_PLAYER"
+        )]
         /// Path to custom player supported by Streamlink (VLC, mpv & more)
         ///
         /// See https://streamlink.github.io/players.html for list of supported players
@@ -165,30 +514,33 @@ pub enum PlayCommand {
 #[derive(StructOpt, Debug, PartialEq, Clone)]
 pub enum RecordCommand {
     #[structopt(
-        usage = "lazystream record select <OUTPUT_DIR> [--restart --proxy <PROXY>] [OPTIONS]"
+        usage = "lazystream record select <OUTPUT_DIR> [--restart --game <N> --match <TEXT> --feed <FEED> --proxy <PROXY>] [OPTIONS]"
     )]
     /// Select a game from the command line to record to OUTPUT DIR
     Select {
-        #[structopt(name = "OUTPUT_DIR", parse(from_os_str))]
-        /// Directory to save game recordings
-        output: PathBuf,
+        #[structopt(flatten)]
+        selection: Selection,
+        #[structopt(name = "OUTPUT_DIR", parse(from_os_str), env = "LAZYSTREAM_OUTPUT_DIR")]
+        /// Directory to save game recordings. Can be left out if output_dir is set in the
+        /// config file
+        output: Option<PathBuf>,
         #[structopt(long)]
         /// If live, restart the stream from the beginning and record the entire thing
         restart: bool,
-        #[structopt(long, parse(try_from_str))]
+        #[structopt(long, parse(try_from_str), env = "LAZYSTREAM_PROXY")]
         /// Proxy server address to be passed to Streamlink
         proxy: Option<Uri>,
         #[structopt(long, value_name = "[HH:]MM:SS", parse(try_from_str = parse_offset))]
         /// Amount of time to skip from the beginning of the stream. For live streams, this is a negative offset from the end of the stream (rewind).
         offset: Option<String>,
-        #[structopt(long)]
+        #[structopt(long, env = "LAZYSTREAM_AUDIO_SOURCE")]
         /// Specify the name / language of the audio source you'd like to use E.g. "en" or "English" for English track
         audio_source: Option<String>,
     },
     #[structopt(
-        usage = "lazystream record team <TEAM> <OUTPUT_DIR> [--restart --feed-type <feed-type> --proxy <PROXY>] [OPTIONS]"
+        usage = "lazystream record team <TEAM> <OUTPUT_DIR> [--restart --feed-type <feed-type> --radio --condensed --game-number <N> --doubleheader --proxy <PROXY>] [OPTIONS]"
     )]
-    /// Specify team abbreviation. If / when stream is available, will record to OUTPUT DIR.
+    /// Specify a team. If / when stream is available, will record to OUTPUT DIR.
     ///
     /// Example: 'lazystream record team VGK /tmp/game.mp4' will download the stream for the
     /// Golden Knights game to /tmp/game.mp4.
@@ -198,25 +550,44 @@ pub enum RecordCommand {
     /// will pass that stream to StreamLink to be downloaded.
     Team {
         #[structopt(name = "TEAM")]
-        /// Team abbreviation
+        /// Team abbreviation, name, city or nickname E.g. VGK, "golden knights" or vegas
         team_abbrev: String,
-        #[structopt(name = "OUTPUT_DIR", parse(from_os_str))]
-        /// Directory to save game recordings
-        output: PathBuf,
+        #[structopt(name = "OUTPUT_DIR", parse(from_os_str), env = "LAZYSTREAM_OUTPUT_DIR")]
+        /// Directory to save game recordings. Can be left out if output_dir is set in the
+        /// config file
+        output: Option<PathBuf>,
         #[structopt(long)]
         /// If live, restart the stream from the beginning and record the entire thing
         restart: bool,
-        #[structopt(long, parse(try_from_str), possible_values(&["HOME", "AWAY", "FRENCH", "COMPOSITE", "NATIONAL"]))]
-        /// Specify the feed type to download. Will default to supplied
-        /// team's applicable Home / Away feed
-        feed_type: Option<FeedType>,
         #[structopt(long, parse(try_from_str))]
+        /// Specify the feed type to download E.g. HOME, AWAY, NATIONAL or IN_MARKET_HOME.
+        /// Must be one of the feeds the game offers. Will default to supplied team's applicable Home / Away feed
+        feed_type: Option<FeedType>,
+        #[structopt(long)]
+        /// Record the radio broadcast to an audio file instead of the video stream
+        radio: bool,
+        #[structopt(long, conflicts_with_all(&["radio", "recap", "highlights", "feed-type"]))]
+        /// Record the condensed game instead of the broadcast. Fails if it hasn't been published yet
+        condensed: bool,
+        #[structopt(long, conflicts_with_all(&["radio", "highlights", "feed-type"]))]
+        /// Record the game recap instead of the broadcast. Fails if it hasn't been published yet
+        recap: bool,
+        #[structopt(long, conflicts_with_all(&["radio", "feed-type"]))]
+        /// Record the extended highlights instead of the broadcast. Fails if they haven't been published yet
+        highlights: bool,
+        #[structopt(long, value_name = "N")]
+        /// Which game of a doubleheader to record E.g. 2. Defaults to the game that's on now or next
+        game_number: Option<u32>,
+        #[structopt(long, conflicts_with = "game-number")]
+        /// Record both games of a doubleheader back to back
+        doubleheader: bool,
+        #[structopt(long, parse(try_from_str), env = "LAZYSTREAM_PROXY")]
         /// Proxy server address to be passed to Streamlink
         proxy: Option<Uri>,
         #[structopt(long, value_name = "[HH:]MM:SS", parse(try_from_str = parse_offset))]
         /// Amount of time to skip from the beginning of the stream. For live streams, this is a negative offset from the end of the stream (rewind).
         offset: Option<String>,
-        #[structopt(long)]
+        #[structopt(long, env = "LAZYSTREAM_AUDIO_SOURCE")]
         /// Specify the name / language of the audio source you'd like to use E.g. "en" or "English" for English track
         audio_source: Option<String>,
     },
@@ -224,55 +595,96 @@ pub enum RecordCommand {
 
 #[derive(StructOpt, Debug, PartialEq, Clone)]
 pub enum CastCommand {
-    #[structopt(usage = "lazystream cast select [--restart --proxy <PROXY>] [OPTIONS]")]
+    #[structopt(
+        usage = "lazystream cast select [--restart --game <N> --match <TEXT> --feed <FEED> --proxy <PROXY>] [OPTIONS]"
+    )]
     /// Select a game and chromecast device from the command line to cast to
     Select {
+        #[structopt(flatten)]
+        selection: Selection,
         #[structopt(long)]
         /// If live, restart the stream from the beginning and cast the entire thing
         restart: bool,
-        #[structopt(long, parse(try_from_str))]
+        #[structopt(long, parse(try_from_str), env = "LAZYSTREAM_PROXY")]
         /// Proxy server address to be passed to Streamlink
         proxy: Option<Uri>,
         #[structopt(long, value_name = "[HH:]MM:SS", parse(try_from_str = parse_offset))]
         /// Amount of time to skip from the beginning of the stream. For live streams, this is a negative offset from the end of the stream (rewind).
         offset: Option<String>,
-        #[structopt(long)]
+        #[structopt(long, env = "LAZYSTREAM_AUDIO_SOURCE")]
         /// Specify the name / language of the audio source you'd like to use E.g. "en" or "English" for English track
         audio_source: Option<String>,
     },
     #[structopt(
-        usage = "lazystream cast team <TEAM> <CHROMECAST_HOST> [--restart --feed-type <feed-type> --proxy <PROXY>] [OPTIONS]"
+        usage = "lazystream cast team <TEAM> [CHROMECAST_HOST] [--restart --feed-type <feed-type> --game-number <N> --doubleheader --proxy <PROXY>] [OPTIONS]"
     )]
-    /// Specify team abbreviation. If / when stream is available, will cast to CHROMECAST_HOST
+    /// Specify a team. If / when stream is available, will cast to CHROMECAST_HOST, or a
+    /// Chromecast picked from the LAN
     ///
     /// Example: 'lazystream cast team VGK 192.16.0.100' will cast the stream for the
     /// Golden Knights game to the Chromecast at 192.168.0.100.
     Team {
         #[structopt(name = "TEAM")]
-        /// Team abbreviation
+        /// Team abbreviation, name, city or nickname E.g. VGK, "golden knights" or vegas
         team_abbrev: String,
-        #[structopt(name = "CHROMECAST_HOST")]
-        /// IP / Hostname of the Chromecast
-        cast_host: String,
+        #[structopt(name = "CHROMECAST_HOST", env = "LAZYSTREAM_CAST_HOST")]
+        /// IP / Hostname of the Chromecast. Will search the LAN for one if not specified
+        cast_host: Option<String>,
         #[structopt(long)]
         /// If live, restart the stream from the beginning and cast the entire thing
         restart: bool,
-        #[structopt(long, parse(try_from_str), possible_values(&["HOME", "AWAY", "FRENCH", "COMPOSITE", "NATIONAL"]))]
-        /// Specify the feed type to cast. Will default to supplied
-        /// team's applicable Home / Away feed
-        feed_type: Option<FeedType>,
         #[structopt(long, parse(try_from_str))]
+        /// Specify the feed type to cast E.g. HOME, AWAY, NATIONAL or IN_MARKET_HOME.
+        /// Must be one of the feeds the game offers. Will default to supplied team's applicable Home / Away feed
+        feed_type: Option<FeedType>,
+        #[structopt(long, value_name = "N")]
+        /// Which game of a doubleheader to cast E.g. 2. Defaults to the game that's on now or next
+        game_number: Option<u32>,
+        #[structopt(long, conflicts_with = "game-number")]
+        /// Cast both games of a doubleheader back to back
+        doubleheader: bool,
+        #[structopt(long, parse(try_from_str), env = "LAZYSTREAM_PROXY")]
         /// Proxy server address to be passed to Streamlink
         proxy: Option<Uri>,
         #[structopt(long, value_name = "[HH:]MM:SS", parse(try_from_str = parse_offset))]
         /// Amount of time to skip from the beginning of the stream. For live streams, this is a negative offset from the end of the stream (rewind).
         offset: Option<String>,
-        #[structopt(long)]
+        #[structopt(long, env = "LAZYSTREAM_AUDIO_SOURCE")]
         /// Specify the name / language of the audio source you'd like to use E.g. "en" or "English" for English track
         audio_source: Option<String>,
     },
 }
 
+#[derive(StructOpt, Debug, PartialEq, Clone)]
+pub enum ConfigCommand {
+    #[structopt(usage = "lazystream config show [--profile <PROFILE>]")]
+    /// Show the effective settings and where each comes from
+    Show,
+    #[structopt(usage = "lazystream config path")]
+    /// Print the path of the config file
+    Path,
+    #[structopt(usage = "lazystream config set <KEY> <VALUE> [--profile <PROFILE>]")]
+    /// Save a setting to the config file, or to the profile if --profile is specified
+    Set {
+        #[structopt(name = "KEY")]
+        /// Setting to save E.g. quality or cast_host, see 'config show' for all settings
+        key: String,
+        #[structopt(name = "VALUE")]
+        /// Value of the setting
+        value: String,
+    },
+    #[structopt(usage = "lazystream config unset <KEY> [--profile <PROFILE>]")]
+    /// Remove a setting from the config file, or from the profile if --profile is specified
+    Unset {
+        #[structopt(name = "KEY")]
+        /// Setting to remove
+        key: String,
+    },
+    #[structopt(usage = "lazystream config edit")]
+    /// Open the config file in $EDITOR
+    Edit,
+}
+
 #[derive(StructOpt, Debug, PartialEq, Clone)]
 pub enum GenerateCommand {
     #[structopt(usage = "lazystream generate playlist <FILE> [OPTIONS]")]
@@ -300,26 +712,143 @@ pub enum GenerateCommand {
 pub enum OutputType {
     Generate(Opt),
     Select(Opt),
+    List(Opt),
+    Browse(Opt),
     Play(Opt),
     Record(Opt),
     Cast(Opt),
+    Config(Opt),
     Completions(Opt),
 }
 
-fn parse_date(src: &str) -> Result<NaiveDate, ParseError> {
-    let s = src.replace("-", "");
-    NaiveDate::parse_from_str(&s, "%Y%m%d")
+/// Inclusive range of days to get games for
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DateRange {
+    pub start: NaiveDate,
+    pub end: NaiveDate,
+}
+
+impl DateRange {
+    /// Maximum number of days in a range, to keep the number of schedule requests sane
+    const MAX_DAYS: i64 = 31;
+
+    pub fn today() -> Self {
+        let today = Local::today().naive_local();
+        DateRange {
+            start: today,
+            end: today,
+        }
+    }
+
+    pub fn is_single_day(&self) -> bool {
+        self.start == self.end
+    }
+
+    /// Every day in the range, in order
+    pub fn days(&self) -> Vec<NaiveDate> {
+        let mut days = vec![];
+        let mut day = self.start;
+        while day <= self.end {
+            days.push(day);
+            day = day.succ();
+        }
+        days
+    }
+}
+
+impl FromStr for DateRange {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<DateRange, Error> {
+        let (start, end) = match s.find("..") {
+            Some(idx) => (parse_date(&s[..idx])?, parse_date(&s[idx + 2..])?),
+            None => {
+                let date = parse_date(s)?;
+                (date, date)
+            }
+        };
+
+        if end < start {
+            bail!("Date range must end on or after the day it starts");
+        }
+        if end.signed_duration_since(start).num_days() >= DateRange::MAX_DAYS {
+            bail!(
+                "Date range can't be longer than {} days",
+                DateRange::MAX_DAYS
+            );
+        }
+
+        Ok(DateRange { start, end })
+    }
+}
+
+impl std::fmt::Display for DateRange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.is_single_day() {
+            write!(f, "{}", self.start.format("%Y-%m-%d"))
+        } else {
+            write!(
+                f,
+                "{} to {}",
+                self.start.format("%Y-%m-%d"),
+                self.end.format("%Y-%m-%d")
+            )
+        }
+    }
+}
+
+/// Parse a single day, either a date or one relative to today
+fn parse_date(src: &str) -> Result<NaiveDate, Error> {
+    let src = src.trim().to_lowercase();
+    let today = Local::today().naive_local();
+
+    match src.as_str() {
+        "today" => return Ok(today),
+        "tomorrow" => return Ok(today.succ()),
+        "yesterday" => return Ok(today.pred()),
+        _ => {}
+    }
+
+    if let Some(days) = src.strip_prefix('+') {
+        return match days.parse::<i64>() {
+            Ok(days) => Ok(today + chrono::Duration::days(days)),
+            Err(_) => bail!("Days from today must be a number E.g. '+3'"),
+        };
+    }
+
+    if let Ok(weekday) = src.parse::<Weekday>() {
+        let days_ahead =
+            (7 + weekday.num_days_from_monday() - today.weekday().num_days_from_monday()) % 7;
+        return Ok(today + chrono::Duration::days(i64::from(days_ahead)));
+    }
+
+    match NaiveDate::parse_from_str(&src.replace("-", ""), "%Y%m%d") {
+        Ok(date) => Ok(date),
+        Err(_) => bail!(
+            "'{}' isn't a valid date. Must be YYYYMMDD, today, tomorrow, yesterday, +N or a weekday",
+            src
+        ),
+    }
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum Cdn {
+    Auto,
     Akc,
     L3c,
 }
 
+impl Cdn {
+    /// All CDNs streams can be served from
+    pub fn all() -> &'static [Cdn] {
+        &[Cdn::Akc, Cdn::L3c]
+    }
+}
+
 impl From<Cdn> for &str {
     fn from(cdn: Cdn) -> &'static str {
         match cdn {
+            Cdn::Auto => "auto",
             Cdn::Akc => "akc",
             Cdn::L3c => "l3c",
         }
@@ -331,9 +860,10 @@ impl FromStr for Cdn {
 
     fn from_str(s: &str) -> Result<Cdn, Error> {
         match s {
+            "auto" => Ok(Cdn::Auto),
             "akc" => Ok(Cdn::Akc),
             "l3c" => Ok(Cdn::L3c),
-            _ => bail!("Option must match 'akc' or 'l3c'"),
+            _ => bail!("Option must match 'auto', 'akc' or 'l3c'"),
         }
     }
 }
@@ -345,92 +875,288 @@ impl std::fmt::Display for Cdn {
     }
 }
 
+/// Quality selector, resolved against the variants in a stream's master playlist
 #[derive(Debug, PartialEq, Clone, Copy)]
 pub enum Quality {
-    _720p60,
-    _720p,
-    _540p,
-    _504p,
-    _360p,
-    _288p,
-    _224p,
-    _216p,
+    /// Highest bitrate variant
+    Best,
+    /// Lowest bitrate variant
+    Worst,
+    /// Exact resolution, E.g. `720p` or `720p60`
+    Resolution {
+        height: u32,
+        frame_rate: Option<u32>,
+    },
+    /// Best variant at or below a resolution, E.g. `<=540p`
+    AtMost(u32),
+    /// Best variant at or below a bitrate, E.g. `max-bitrate=3M`
+    MaxBitrate(u64),
+    /// Best variant with a frame rate, E.g. `60fps`
+    FrameRate(u32),
+}
+
+impl FromStr for Quality {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Quality, Error> {
+        let resolution = regex::Regex::new(r"^(\d+)p(\d+)?$").unwrap();
+        let at_most = regex::Regex::new(r"^<=(\d+)p$").unwrap();
+        let max_bitrate = regex::Regex::new(r"^max-bitrate=(\d+(?:\.\d+)?)([kKmM]?)$").unwrap();
+        let frame_rate = regex::Regex::new(r"^(\d+)fps$").unwrap();
+
+        let s = s.trim().to_lowercase();
+        let quality = if s == "best" {
+            Quality::Best
+        } else if s == "worst" {
+            Quality::Worst
+        } else if let Some(caps) = resolution.captures(&s) {
+            Quality::Resolution {
+                height: caps[1].parse()?,
+                frame_rate: caps.get(2).map(|rate| rate.as_str().parse()).transpose()?,
+            }
+        } else if let Some(caps) = at_most.captures(&s) {
+            Quality::AtMost(caps[1].parse()?)
+        } else if let Some(caps) = max_bitrate.captures(&s) {
+            let multiplier = match &caps[2] {
+                "k" => 1_000.0,
+                "m" => 1_000_000.0,
+                _ => 1.0,
+            };
+            Quality::MaxBitrate((caps[1].parse::<f64>()? * multiplier) as u64)
+        } else if let Some(caps) = frame_rate.captures(&s) {
+            Quality::FrameRate(caps[1].parse()?)
+        } else {
+            bail!(
+                "Must be one of: 'best', 'worst', '<HEIGHT>p[FPS]' (720p60), \
+                 '<=<HEIGHT>p' (<=540p), 'max-bitrate=<BITRATE>' (max-bitrate=3M) \
+                 or '<FPS>fps' (60fps)"
+            )
+        };
+
+        Ok(quality)
+    }
 }
 
-impl From<Quality> for &str {
-    fn from(quality: Quality) -> &'static str {
-        match quality {
-            Quality::_720p60 => "72060",
-            Quality::_720p => "720",
-            Quality::_540p => "540",
-            Quality::_504p => "504",
-            Quality::_360p => "360",
-            Quality::_288p => "288",
-            Quality::_224p => "224",
-            Quality::_216p => "216",
+impl std::fmt::Display for Quality {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Quality::Best => write!(f, "best"),
+            Quality::Worst => write!(f, "worst"),
+            Quality::Resolution {
+                height,
+                frame_rate: Some(frame_rate),
+            } => write!(f, "{}p{}", height, frame_rate),
+            Quality::Resolution { height, .. } => write!(f, "{}p", height),
+            Quality::AtMost(height) => write!(f, "<={}p", height),
+            Quality::MaxBitrate(bitrate) => write!(f, "max-bitrate={}", bitrate),
+            Quality::FrameRate(frame_rate) => write!(f, "{}fps", frame_rate),
         }
     }
 }
 
-impl FromStr for Quality {
+impl Quality {
+    /// Select the variant matching this quality from the master playlist variants
+    pub fn select(self, variants: &[Variant]) -> Option<&Variant> {
+        let matching = variants.iter().filter(|variant| match self {
+            Quality::Best | Quality::Worst => true,
+            Quality::Resolution { height, frame_rate } => {
+                variant.height() == height
+                    && match frame_rate {
+                        Some(frame_rate) => variant.rounded_frame_rate() == frame_rate,
+                        None => variant.rounded_frame_rate() <= 30,
+                    }
+            }
+            Quality::AtMost(height) => variant.height() > 0 && variant.height() <= height,
+            Quality::MaxBitrate(bitrate) => variant.bandwidth <= bitrate,
+            Quality::FrameRate(frame_rate) => variant.rounded_frame_rate() == frame_rate,
+        });
+
+        match self {
+            Quality::Worst => matching.min_by_key(|variant| variant.bandwidth),
+            Quality::AtMost(_) => {
+                matching.max_by_key(|variant| (variant.height(), variant.bandwidth))
+            }
+            _ => matching.max_by_key(|variant| variant.bandwidth),
+        }
+    }
+
+    /// Select the nearest variant below or above this quality, used when no
+    /// variant matches it
+    pub fn fallback(self, variants: &[Variant], fallback: QualityFallback) -> Option<&Variant> {
+        let rank = |variant: &&Variant| {
+            (
+                variant.height(),
+                variant.rounded_frame_rate(),
+                variant.bandwidth,
+            )
+        };
+
+        let candidates =
+            variants
+                .iter()
+                .filter(move |variant| match (fallback, self.compare(variant)) {
+                    (QualityFallback::Lower, Some(ordering)) => ordering == Ordering::Less,
+                    (QualityFallback::Higher, Some(ordering)) => ordering == Ordering::Greater,
+                    _ => false,
+                });
+
+        if fallback == QualityFallback::Lower {
+            candidates.max_by_key(rank)
+        } else {
+            candidates.min_by_key(rank)
+        }
+    }
+
+    /// How a variant compares to this quality, `None` if qualities can't be ordered
+    fn compare(self, variant: &Variant) -> Option<Ordering> {
+        match self {
+            Quality::Best | Quality::Worst => None,
+            Quality::Resolution { height, frame_rate } => Some(
+                (variant.height(), variant.rounded_frame_rate())
+                    .cmp(&(height, frame_rate.unwrap_or(30))),
+            ),
+            Quality::AtMost(height) => Some(variant.height().cmp(&height)),
+            Quality::MaxBitrate(bitrate) => Some(variant.bandwidth.cmp(&bitrate)),
+            Quality::FrameRate(frame_rate) => Some(variant.rounded_frame_rate().cmp(&frame_rate)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ErrorFormat {
+    Text,
+    Json,
+}
+
+impl From<ErrorFormat> for &str {
+    fn from(format: ErrorFormat) -> &'static str {
+        match format {
+            ErrorFormat::Text => "text",
+            ErrorFormat::Json => "json",
+        }
+    }
+}
+
+impl FromStr for ErrorFormat {
     type Err = Error;
 
-    fn from_str(s: &str) -> Result<Quality, Error> {
+    fn from_str(s: &str) -> Result<ErrorFormat, Error> {
         match s {
-            "720p60" => Ok(Quality::_720p60),
-            "720p" => Ok(Quality::_720p),
-            "540p" => Ok(Quality::_540p),
-            "504p" => Ok(Quality::_504p),
-            "360p" => Ok(Quality::_360p),
-            "288p" => Ok(Quality::_288p),
-            "224p" => Ok(Quality::_224p),
-            "216p" => Ok(Quality::_216p),
-            _ => bail!(
-                "Must be one of: '720p60', '720p', '540p', '504p', '360p', '288p', '224p', '216p'"
-            ),
+            "text" => Ok(ErrorFormat::Text),
+            "json" => Ok(ErrorFormat::Json),
+            _ => bail!("Option must match 'text' or 'json'"),
         }
     }
 }
 
-impl std::fmt::Display for Quality {
+impl std::fmt::Display for ErrorFormat {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let s: &str = self.clone().into();
+        let s: &str = (*self).into();
         write!(f, "{}", s)
     }
 }
 
-impl Quality {
-    pub fn to_streamlink_quality<'a>(self) -> &'a str {
-        match self {
-            Quality::_720p60 => "720p_alt",
-            Quality::_720p => "720p",
-            Quality::_540p => "540p",
-            Quality::_504p => "504p",
-            Quality::_360p => "360p",
-            Quality::_288p => "288p",
-            Quality::_224p => "224p",
-            Quality::_216p => "216p",
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ListFormat {
+    Table,
+    Json,
+    Csv,
+    Tsv,
+}
+
+impl From<ListFormat> for &str {
+    fn from(format: ListFormat) -> &'static str {
+        match format {
+            ListFormat::Table => "table",
+            ListFormat::Json => "json",
+            ListFormat::Csv => "csv",
+            ListFormat::Tsv => "tsv",
         }
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Hash, Eq, PartialOrd, Ord)]
+impl FromStr for ListFormat {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<ListFormat, Error> {
+        match s {
+            "table" => Ok(ListFormat::Table),
+            "json" => Ok(ListFormat::Json),
+            "csv" => Ok(ListFormat::Csv),
+            "tsv" => Ok(ListFormat::Tsv),
+            _ => bail!("Option must match 'table', 'json', 'csv' or 'tsv'"),
+        }
+    }
+}
+
+impl std::fmt::Display for ListFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s: &str = (*self).into();
+        write!(f, "{}", s)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum QualityFallback {
+    Lower,
+    Higher,
+    Adaptive,
+    None,
+}
+
+impl From<QualityFallback> for &str {
+    fn from(fallback: QualityFallback) -> &'static str {
+        match fallback {
+            QualityFallback::Lower => "lower",
+            QualityFallback::Higher => "higher",
+            QualityFallback::Adaptive => "adaptive",
+            QualityFallback::None => "none",
+        }
+    }
+}
+
+impl FromStr for QualityFallback {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<QualityFallback, Error> {
+        match s {
+            "lower" => Ok(QualityFallback::Lower),
+            "higher" => Ok(QualityFallback::Higher),
+            "adaptive" => Ok(QualityFallback::Adaptive),
+            "none" => Ok(QualityFallback::None),
+            _ => bail!("Must be one of: 'lower', 'higher', 'adaptive', 'none'"),
+        }
+    }
+}
+
+impl std::fmt::Display for QualityFallback {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s: &str = (*self).into();
+        write!(f, "{}", s)
+    }
+}
+
+/// Feed type of a stream. Feed types lazystream doesn't know about, E.g. MLB's
+/// `IN_MARKET_HOME`, are kept as `Other`
+#[derive(Debug, Clone, PartialEq, Hash, Eq, PartialOrd, Ord)]
 pub enum FeedType {
     National,
     Home,
     Away,
     French,
     Composite,
+    Other(String),
 }
 
-impl From<FeedType> for &str {
-    fn from(feed_type: FeedType) -> &'static str {
-        match feed_type {
+impl FeedType {
+    pub fn as_str(&self) -> &str {
+        match self {
             FeedType::Home => "HOME",
             FeedType::Away => "AWAY",
             FeedType::National => "NATIONAL",
             FeedType::French => "FRENCH",
             FeedType::Composite => "COMPOSITE",
+            FeedType::Other(feed_type) => feed_type,
         }
     }
 }
@@ -439,21 +1165,22 @@ impl FromStr for FeedType {
     type Err = Error;
 
     fn from_str(s: &str) -> Result<FeedType, Error> {
-        match s {
+        let s = s.trim().to_uppercase();
+        match s.as_str() {
             "HOME" => Ok(FeedType::Home),
             "AWAY" => Ok(FeedType::Away),
             "FRENCH" => Ok(FeedType::French),
             "COMPOSITE" => Ok(FeedType::Composite),
             "NATIONAL" => Ok(FeedType::National),
-            _ => bail!("Must be one of: 'HOME', 'AWAY', 'FRENCH', 'COMPOSITE', 'NATIONAL'"),
+            "" => bail!("Feed type can't be empty"),
+            _ => Ok(FeedType::Other(s)),
         }
     }
 }
 
 impl std::fmt::Display for FeedType {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let s: &str = self.clone().into();
-        write!(f, "{}", s)
+        write!(f, "{}", self.as_str())
     }
 }
 
@@ -498,3 +1225,206 @@ impl std::fmt::Display for Sport {
         write!(f, "{}", s)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hls::Resolution;
+
+    fn video(height: u32, frame_rate: f64, bandwidth: u64) -> Variant {
+        Variant {
+            uri: format!("https://hls.example.com/{}p.m3u8", height),
+            bandwidth,
+            average_bandwidth: None,
+            resolution: Some(Resolution {
+                width: height * 16 / 9,
+                height,
+            }),
+            frame_rate: Some(frame_rate),
+            codecs: None,
+            audio: None,
+        }
+    }
+
+    fn variants() -> Vec<Variant> {
+        vec![
+            video(1080, 59.94, 6_600_000),
+            video(720, 59.94, 4_500_000),
+            video(720, 29.97, 3_000_000),
+            video(540, 29.97, 2_000_000),
+            video(360, 29.97, 1_200_000),
+            Variant {
+                uri: String::from("https://hls.example.com/audio.m3u8"),
+                bandwidth: 192_000,
+                average_bandwidth: None,
+                resolution: None,
+                frame_rate: None,
+                codecs: None,
+                audio: None,
+            },
+        ]
+    }
+
+    fn select(quality: &str) -> Option<String> {
+        let variants = variants();
+        let quality: Quality = quality.parse().unwrap();
+        quality.select(&variants).map(Variant::name)
+    }
+
+    fn fallback(quality: &str, fallback: QualityFallback) -> Option<String> {
+        let variants = variants();
+        let quality: Quality = quality.parse().unwrap();
+        quality.fallback(&variants, fallback).map(Variant::name)
+    }
+
+    #[test]
+    fn parses_qualities() {
+        let parse = |s: &str| s.parse::<Quality>().unwrap();
+
+        assert_eq!(parse("best"), Quality::Best);
+        assert_eq!(parse("WORST"), Quality::Worst);
+        assert_eq!(
+            parse("720p60"),
+            Quality::Resolution {
+                height: 720,
+                frame_rate: Some(60)
+            }
+        );
+        assert_eq!(
+            parse(" 540p "),
+            Quality::Resolution {
+                height: 540,
+                frame_rate: None
+            }
+        );
+        assert_eq!(parse("<=540p"), Quality::AtMost(540));
+        assert_eq!(parse("max-bitrate=3M"), Quality::MaxBitrate(3_000_000));
+        assert_eq!(parse("max-bitrate=1.5m"), Quality::MaxBitrate(1_500_000));
+        assert_eq!(parse("max-bitrate=800k"), Quality::MaxBitrate(800_000));
+        assert_eq!(parse("max-bitrate=250000"), Quality::MaxBitrate(250_000));
+        assert_eq!(parse("60fps"), Quality::FrameRate(60));
+
+        for invalid in &["", "720", "hd", "p60", "<=540", "max-bitrate=3G", "60 fps"] {
+            assert!(invalid.parse::<Quality>().is_err(), "{} parsed", invalid);
+        }
+    }
+
+    #[test]
+    fn displays_parseable_qualities() {
+        for quality in &["best", "worst", "720p60", "540p", "<=540p", "60fps"] {
+            assert_eq!(quality.parse::<Quality>().unwrap().to_string(), *quality);
+        }
+
+        let quality: Quality = "max-bitrate=3M".parse().unwrap();
+        assert_eq!(quality.to_string().parse::<Quality>().unwrap(), quality);
+    }
+
+    #[test]
+    fn selects_variants() {
+        assert_eq!(select("best").unwrap(), "1080p60 (6.6 Mbps)");
+        assert_eq!(select("worst").unwrap(), "0.2 Mbps");
+        assert_eq!(select("720p60").unwrap(), "720p60 (4.5 Mbps)");
+        assert_eq!(select("720p").unwrap(), "720p (3.0 Mbps)");
+        assert_eq!(select("<=540p").unwrap(), "540p (2.0 Mbps)");
+        assert_eq!(select("<=600p").unwrap(), "540p (2.0 Mbps)");
+        assert_eq!(select("max-bitrate=3M").unwrap(), "720p (3.0 Mbps)");
+        assert_eq!(select("60fps").unwrap(), "1080p60 (6.6 Mbps)");
+
+        assert_eq!(select("480p"), None);
+        assert_eq!(select("540p60"), None);
+        assert_eq!(select("<=240p"), None);
+        assert_eq!(select("max-bitrate=100k"), None);
+    }
+
+    #[test]
+    fn falls_back_to_nearest_variant() {
+        assert_eq!(
+            fallback("480p", QualityFallback::Lower).unwrap(),
+            "360p (1.2 Mbps)"
+        );
+        assert_eq!(
+            fallback("480p", QualityFallback::Higher).unwrap(),
+            "540p (2.0 Mbps)"
+        );
+        assert_eq!(
+            fallback("1440p", QualityFallback::Lower).unwrap(),
+            "1080p60 (6.6 Mbps)"
+        );
+        assert_eq!(fallback("1440p", QualityFallback::Higher), None);
+        assert_eq!(fallback("480p", QualityFallback::Adaptive), None);
+        assert_eq!(fallback("480p", QualityFallback::None), None);
+        assert_eq!(fallback("best", QualityFallback::Lower), None);
+    }
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd(year, month, day)
+    }
+
+    #[test]
+    fn parses_dates() {
+        let range: DateRange = "20191208".parse().unwrap();
+        assert_eq!(range.start, date(2019, 12, 8));
+        assert!(range.is_single_day());
+        assert_eq!(range.to_string(), "2019-12-08");
+
+        let range: DateRange = "2019-12-08".parse().unwrap();
+        assert_eq!(range.days(), vec![date(2019, 12, 8)]);
+
+        let today = Local::today().naive_local();
+        assert_eq!("today".parse::<DateRange>().unwrap(), DateRange::today());
+        assert_eq!("Tomorrow".parse::<DateRange>().unwrap().start, today.succ());
+        assert_eq!(
+            "yesterday".parse::<DateRange>().unwrap().start,
+            today.pred()
+        );
+        assert_eq!(
+            "+3".parse::<DateRange>().unwrap().start,
+            today + chrono::Duration::days(3)
+        );
+
+        let saturday = "sat".parse::<DateRange>().unwrap().start;
+        assert_eq!(saturday.weekday(), Weekday::Sat);
+        assert!(saturday >= today && saturday < today + chrono::Duration::days(7));
+
+        for invalid in &["", "2019-13-01", "20191232", "+three", "someday"] {
+            assert!(invalid.parse::<DateRange>().is_err(), "{} parsed", invalid);
+        }
+    }
+
+    #[test]
+    fn parses_date_ranges() {
+        let range: DateRange = "2019-12-01..2019-12-07".parse().unwrap();
+        assert_eq!(range.start, date(2019, 12, 1));
+        assert_eq!(range.end, date(2019, 12, 7));
+        assert!(!range.is_single_day());
+        assert_eq!(range.days().len(), 7);
+        assert_eq!(range.to_string(), "2019-12-01 to 2019-12-07");
+
+        let range: DateRange = "yesterday..today".parse().unwrap();
+        assert_eq!(range.days().len(), 2);
+        assert_eq!(range.end, DateRange::today().end);
+
+        let range: DateRange = "2019-12-08..2019-12-08".parse().unwrap();
+        assert!(range.is_single_day());
+
+        // Ranges can't be open or backwards
+        for invalid in &[
+            "..2019-12-07",
+            "2019-12-01..",
+            "..",
+            "2019-12-07..2019-12-01",
+            "2019-12-01...2019-12-07",
+        ] {
+            assert!(invalid.parse::<DateRange>().is_err(), "{} parsed", invalid);
+        }
+    }
+
+    #[test]
+    fn limits_date_ranges_to_31_days() {
+        let range: DateRange = "2019-12-01..2019-12-31".parse().unwrap();
+        assert_eq!(range.days().len(), 31);
+
+        assert!("2019-12-01..2020-01-01".parse::<DateRange>().is_err());
+        assert!("2019-01-01..2019-12-31".parse::<DateRange>().is_err());
+    }
+}
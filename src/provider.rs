@@ -0,0 +1,152 @@
+use crate::{league::LeagueProvider, opt::Cdn};
+use chrono::NaiveDate;
+use failure::{bail, format_err, Error};
+use http::Uri;
+use std::str::FromStr;
+
+/// Link LazyMan style hosts serve master links from
+pub const DEFAULT_HOST_TEMPLATE: &str =
+    "{host}/getM3U8.php?league={league}&date={date}&id={id}&cdn={cdn}";
+
+const PLACEHOLDERS: &[&str] = &["host", "league", "date", "id", "cdn"];
+
+/// A host that resolves a stream id to the master m3u8 link for that stream
+pub trait StreamProvider: Send + Sync {
+    /// Url that will be requested to get the master link of a stream
+    fn host_link(&self, league: &dyn LeagueProvider, date: NaiveDate, id: &str, cdn: Cdn)
+        -> String;
+
+    /// Parse the master link from the host response. Returns `None` if
+    /// the stream is not available yet
+    fn parse_master_link(&self, body: &str) -> Option<String>;
+}
+
+/// Link requested to resolve the master link of a stream, E.g.
+/// `{host}/getM3U8.php?league={league}&date={date}&id={id}&cdn={cdn}`. Placeholders are
+/// replaced with the stream's values
+#[derive(Debug, Clone, PartialEq)]
+pub struct HostTemplate(String);
+
+impl FromStr for HostTemplate {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<HostTemplate, Error> {
+        let mut rest = s;
+        while let Some(start) = rest.find('{') {
+            let end = rest[start..]
+                .find('}')
+                .ok_or_else(|| format_err!("Placeholder isn't closed in {}", s))?;
+            let placeholder = &rest[start + 1..start + end];
+            if !PLACEHOLDERS.contains(&placeholder) {
+                bail!(
+                    "{{{}}} isn't a placeholder, placeholders are: {{{}}}",
+                    placeholder,
+                    PLACEHOLDERS.join("}, {")
+                );
+            }
+
+            rest = &rest[start + end + 1..];
+        }
+
+        // Without the id, every stream would resolve to the same link
+        if !s.contains("{id}") {
+            bail!("Template must contain the {{id}} placeholder");
+        }
+
+        Ok(HostTemplate(s.to_owned()))
+    }
+}
+
+/// LazyMan style host, serving master links from the link of its template
+pub struct Lazyman {
+    host: String,
+    template: HostTemplate,
+}
+
+impl Lazyman {
+    pub fn new(host: &str, template: &HostTemplate) -> Self {
+        Lazyman {
+            host: host.trim_end_matches('/').to_owned(),
+            template: template.clone(),
+        }
+    }
+}
+
+impl StreamProvider for Lazyman {
+    fn host_link(
+        &self,
+        league: &dyn LeagueProvider,
+        date: NaiveDate,
+        id: &str,
+        cdn: Cdn,
+    ) -> String {
+        self.template
+            .0
+            .replace("{host}", &self.host)
+            .replace("{league}", &league.sport().to_string())
+            .replace("{date}", &date.format("%Y-%m-%d").to_string())
+            .replace("{id}", id)
+            .replace("{cdn}", &cdn.to_string())
+    }
+
+    fn parse_master_link(&self, body: &str) -> Option<String> {
+        let body = body.trim();
+
+        // Anything else is the host's message that the stream isn't available yet
+        match body.parse::<Uri>() {
+            Ok(uri) if matches!(uri.scheme_str(), Some("http") | Some("https")) => {
+                Some(body.to_owned())
+            }
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::league::{Mlb, Nhl};
+
+    #[test]
+    fn substitutes_placeholders() {
+        let template = DEFAULT_HOST_TEMPLATE.parse().unwrap();
+        let provider = Lazyman::new("http://localhost:8080/", &template);
+        let date = NaiveDate::from_ymd(2019, 12, 8);
+
+        assert_eq!(
+            provider.host_link(&Nhl::default(), date, "12345", Cdn::Akc),
+            "http://localhost:8080/getM3U8.php?league=nhl&date=2019-12-08&id=12345&cdn=akc"
+        );
+
+        let template = "{host}/m3u8/{league}/{id}".parse().unwrap();
+        let provider = Lazyman::new("http://mirror", &template);
+        assert_eq!(
+            provider.host_link(&Mlb::default(), date, "678", Cdn::L3c),
+            "http://mirror/m3u8/MLB/678"
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_templates() {
+        assert!("{host}/getM3U8.php?id={id}&feed={feed}"
+            .parse::<HostTemplate>()
+            .is_err());
+        assert!("{host}/getM3U8.php?id={id".parse::<HostTemplate>().is_err());
+        assert!("{host}/getM3U8.php?league={league}"
+            .parse::<HostTemplate>()
+            .is_err());
+    }
+
+    #[test]
+    fn parses_master_link() {
+        let template = DEFAULT_HOST_TEMPLATE.parse().unwrap();
+        let provider = Lazyman::new("http://localhost", &template);
+
+        assert_eq!(
+            provider.parse_master_link("http://cdn.example/master.m3u8\n"),
+            Some(String::from("http://cdn.example/master.m3u8"))
+        );
+        assert_eq!(provider.parse_master_link("Not available yet"), None);
+        assert_eq!(provider.parse_master_link(""), None);
+    }
+}
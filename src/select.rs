@@ -1,31 +1,44 @@
 use crate::{
-    log_error,
-    opt::{Command, FeedType, Opt},
-    stream::{Game, LazyStream, Stream},
+    error::LazyStreamError,
+    exit_with_error,
+    opt::{Command, Opt, Selection},
+    stream::{Game, LazyStream, Stream, StreamKind},
     BANNER,
 };
-use async_std::{process, task};
+use async_std::task;
 use chrono::Local;
-use failure::{bail, Error};
+use failure::Error;
 use read_input::prelude::*;
 
 pub fn run(opts: Opt) {
+    let selection = if let Command::Select { selection, .. } = &opts.command {
+        selection.clone()
+    } else {
+        Selection::default()
+    };
+
     task::block_on(async {
-        if let Err(e) = process(&opts, false).await {
-            log_error(&e);
-            process::exit(1);
+        if let Err(e) = process(&opts, &selection, false).await {
+            exit_with_error(&e, opts.error_format);
         };
     });
 
-    if cfg!(target_os = "windows") {
+    // Only keep the window open when picking interactively, scripts shouldn't block
+    if cfg!(target_os = "windows") && selection == Selection::default() {
         pause();
     }
 }
 
-pub async fn process(opts: &Opt, need_return: bool) -> Result<(Game, Stream), Error> {
+/// Pick a game and one of its streams. Whatever `selection` doesn't specify is
+/// prompted for
+pub async fn process(
+    opts: &Opt,
+    selection: &Selection,
+    need_return: bool,
+) -> Result<(Game, Stream), Error> {
     println!("{}", BANNER);
 
-    let resolve = if let Command::Select { resolve } = opts.command {
+    let resolve = if let Command::Select { resolve, .. } = opts.command {
         resolve
     } else {
         false
@@ -34,60 +47,97 @@ pub async fn process(opts: &Opt, need_return: bool) -> Result<(Game, Stream), Er
     let lazy_stream = LazyStream::new(opts).await?;
     let mut games = lazy_stream.games();
 
-    println!(
-        "\nPick a game for {}...\n",
-        lazy_stream.date().format("%Y-%m-%d")
-    );
-    for (idx, game) in games.iter().enumerate() {
-        println!(
-            "{}) {} - {} @ {}",
-            idx + 1,
-            game.game_date
-                .with_timezone(&Local)
-                .time()
-                .format("%-I:%M %p")
-                .to_string(),
-            game.away_team.name,
-            game.home_team.name
-        );
-    }
+    let date_range = lazy_stream.date_range();
 
-    let game_count = games.len();
-    let game_choice = input::<usize>()
-        .msg("\n>>> ")
-        .add_test(move |input| *input > 0 && *input <= game_count)
-        .get();
+    // Games on different days need the day shown to tell them apart
+    let time_format = if date_range.is_single_day() {
+        "%-I:%M %p"
+    } else {
+        "%a %b %-d %-I:%M %p"
+    };
+    let game_labels: Vec<_> = games
+        .iter()
+        .enumerate()
+        .map(|(idx, game)| {
+            format!(
+                "{}) {} - {} @ {}{}",
+                idx + 1,
+                game.game_date
+                    .with_timezone(&Local)
+                    .format(time_format)
+                    .to_string(),
+                game.away_team.name,
+                game.home_team.name,
+                game.game_number_suffix(),
+            )
+        })
+        .collect();
+
+    let game_choice = if let Some(game_choice) = selected_game(&games, selection)? {
+        println!("\nUsing game {}", game_labels[game_choice - 1]);
+        game_choice
+    } else {
+        println!("\nPick a game for {}...\n", date_range);
+        for label in &game_labels {
+            println!("{}", label);
+        }
+
+        let game_count = games.len();
+        input::<usize>()
+            .msg("\n>>> ")
+            .add_test(move |input| *input > 0 && *input <= game_count)
+            .get()
+    };
     let mut game = games.remove(game_choice - 1);
 
-    let mut streams = game.streams().await?;
+    let mut streams: Vec<Stream> = game
+        .streams()
+        .await?
+        .into_iter()
+        .map(|(_, stream)| stream)
+        .collect();
+    streams.append(&mut game.audio_streams().await?);
+    streams.append(&mut game.vod_streams().await?);
 
     if streams.is_empty() {
-        bail!("No streams available for that game");
+        return Err(LazyStreamError::NoStreams.into());
     }
 
-    println!("\nPick a stream...\n");
-
-    let feeds: Vec<FeedType> = streams.clone().into_iter().map(|(k, _)| k).collect();
+    let feed_choice = if let Some(feed) = &selection.feed {
+        let feed_choice = selected_feed(&streams, feed)?;
+        println!("Using stream {}", streams[feed_choice - 1].label());
+        feed_choice
+    } else {
+        println!("\nPick a stream...\n");
 
-    for (idx, feed_type) in feeds.iter().enumerate() {
-        println!("{}) {}", idx + 1, feed_type);
-    }
+        for (idx, stream) in streams.iter().enumerate() {
+            println!("{}) {}", idx + 1, stream.label());
+        }
 
-    let feed_count = feeds.len();
-    let feed_choice = input::<usize>()
-        .msg("\n>>> ")
-        .add_test(move |input| *input > 0 && *input <= feed_count)
-        .get();
-    let feed_choice = &feeds[(feed_choice - 1)];
-    let mut stream = streams.remove(feed_choice).unwrap();
+        let feed_count = streams.len();
+        input::<usize>()
+            .msg("\n>>> ")
+            .add_test(move |input| *input > 0 && *input <= feed_count)
+            .get()
+    };
+    let mut stream = streams.remove(feed_choice - 1);
 
     let host_link = stream.host_link(lazy_stream.opts.cdn);
 
     let cdn = lazy_stream.opts.cdn;
     if !need_return {
         println!();
-        if let Some(quality) = lazy_stream.opts.quality {
-            let quality_link = stream.quality_link(cdn, quality).await?;
+        let quality = lazy_stream
+            .opts
+            .quality
+            .filter(|_| stream.kind == StreamKind::Video);
+        if let Some(quality) = quality {
+            let quality_link = stream
+                .quality_link(cdn, quality, lazy_stream.opts.quality_fallback)
+                .await?;
+            if let Some(rendition) = stream.quality_fallback() {
+                println!("{} not available, using {}", quality, rendition);
+            }
             println!("{}", quality_link);
         } else if resolve {
             let master_link = stream.master_link(cdn).await?;
@@ -100,6 +150,76 @@ pub async fn process(opts: &Opt, need_return: bool) -> Result<(Game, Stream), Er
     Ok((game, stream))
 }
 
+/// Number of the game picked by `--game` or `--match`, `None` if neither was specified
+fn selected_game(games: &[Game], selection: &Selection) -> Result<Option<usize>, Error> {
+    if let Some(game_choice) = selection.game {
+        if game_choice == 0 || game_choice > games.len() {
+            return Err(LazyStreamError::NoMatchingGame(format!("number {}", game_choice)).into());
+        }
+        return Ok(Some(game_choice));
+    }
+
+    let text = match &selection.game_match {
+        Some(text) => text,
+        None => return Ok(None),
+    };
+
+    let matching: Vec<_> = games
+        .iter()
+        .enumerate()
+        .filter(|(_, game)| {
+            [&game.home_team, &game.away_team].iter().any(|team| {
+                team.abbreviation.eq_ignore_ascii_case(text)
+                    || team.name.to_lowercase().contains(&text.to_lowercase())
+            })
+        })
+        .collect();
+
+    match matching.as_slice() {
+        [] => Err(LazyStreamError::NoMatchingGame(text.clone()).into()),
+        [(idx, _)] => Ok(Some(idx + 1)),
+        _ => {
+            let found: Vec<_> = matching
+                .iter()
+                .map(|(_, game)| {
+                    format!(
+                        "{} @ {}{}",
+                        game.away_team.abbreviation,
+                        game.home_team.abbreviation,
+                        game.game_number_suffix()
+                    )
+                })
+                .collect();
+            Err(LazyStreamError::AmbiguousGame(text.clone(), found.join(", ")).into())
+        }
+    }
+}
+
+/// Number of the stream picked by `--feed`. Matches the number in the list, then the
+/// feed name E.g. `HOME - SN`, then the feed type E.g. `HOME`
+fn selected_feed(streams: &[Stream], feed: &str) -> Result<usize, Error> {
+    let feed = feed.trim();
+
+    let position = if let Ok(feed_choice) = feed.parse::<usize>() {
+        Some(feed_choice).filter(|choice| *choice > 0 && *choice <= streams.len())
+    } else {
+        streams
+            .iter()
+            .position(|stream| stream.feed_name().eq_ignore_ascii_case(feed))
+            .or_else(|| {
+                streams
+                    .iter()
+                    .position(|stream| stream.feed_type.as_str().eq_ignore_ascii_case(feed))
+            })
+            .map(|idx| idx + 1)
+    };
+
+    position.ok_or_else(|| {
+        let available: Vec<_> = streams.iter().map(Stream::feed_name).collect();
+        LazyStreamError::FeedNotAvailable(feed.to_owned(), available.join(", ")).into()
+    })
+}
+
 // Keep console window open until button press
 fn pause() {
     use std::io::{self, prelude::*};
@@ -2,103 +2,172 @@ use crate::{
     api::{
         client::Client,
         model::{
-            GameContentArticleMediaImageCut, GameContentEditorialItem, GameContentResponse, Team,
+            GameContentArticleMediaImageCut, GameContentEditorialItem, GameContentEpgItem,
+            GameContentResponse, ScheduleGameStatus, ScheduleGameTeamDetail, Team,
         },
     },
-    opt::{Cdn, FeedType, Opt, Quality, Sport},
-    HOST,
+    error::LazyStreamError,
+    hls::MasterPlaylist,
+    league::LeagueProvider,
+    net::HttpContext,
+    opt::{Cdn, DateRange, FeedType, GameFilter, GameType, Opt, Quality, QualityFallback},
+    provider::StreamProvider,
+    team,
 };
 use chrono::{DateTime, Local, NaiveDate, Utc};
-use failure::{bail, format_err, Error, ResultExt};
-use futures::{future, AsyncReadExt};
-use http_client::{native::NativeClient, Body, HttpClient};
-use std::{collections::BTreeMap, str::FromStr};
+use failure::{Error, ResultExt};
+use futures::future;
+use std::{collections::BTreeMap, str::FromStr, sync::Arc, time::Instant};
 
 pub struct LazyStream {
     pub opts: Opt,
+    client: Arc<Client>,
     games: Vec<Game>,
     teams: Vec<Team>,
 }
 
 impl LazyStream {
     pub async fn new(opts: &Opt) -> Result<Self, Error> {
-        let date = if opts.date.is_some() {
-            opts.date.clone().unwrap()
-        } else {
-            Local::today().naive_local()
-        };
+        let days = opts.date_range().days();
 
-        let client = Client::new(opts.sport);
-        let schedule = client.get_schedule_for(date).await?;
+        let client = Arc::new(Client::new(
+            opts.league_provider()?,
+            opts.http_context(),
+            opts.cache(),
+        ));
+        let provider = opts.stream_provider();
+        let schedules =
+            future::try_join_all(days.iter().map(|date| client.get_schedule_for(*date))).await?;
         let teams = client.get_teams().await?;
 
         let mut games = vec![];
-        for game in schedule.games {
-            let game_pk = game.game_pk;
-            let game_date = game.date;
-            let home_team = teams
-                .iter()
-                .find(|team| team.id == game.teams.home.detail.id)
-                .unwrap();
-            let away_team = teams
-                .iter()
-                .find(|team| team.id == game.teams.away.detail.id)
-                .unwrap();
-
-            let game = Game::new(
-                opts.sport,
-                game_pk,
-                game_date,
-                date,
-                home_team.clone(),
-                away_team.clone(),
-            );
-            games.push(game);
+        for (date, schedule) in days.into_iter().zip(schedules) {
+            for game in schedule.games {
+                let game_pk = game.game_pk;
+                let game_date = game.date;
+                let status = game.status.clone();
+                let game_type = game.game_type.clone();
+                let season = game.season.clone();
+                let game_number = game.game_number;
+                let home_team = find_team(&teams, &game.teams.home.detail);
+                let away_team = find_team(&teams, &game.teams.away.detail);
+
+                let game = Game::new(
+                    client.clone(),
+                    provider.clone(),
+                    game_pk,
+                    game_date,
+                    status,
+                    game_type,
+                    season,
+                    game_number,
+                    date,
+                    home_team,
+                    away_team,
+                );
+                games.push(game);
+            }
         }
         games.sort_by_key(|game| (game.game_date, game.away_team.name.clone()));
+        number_doubleheaders(&mut games);
+
+        // Teams to filter by can be typed like any other team, so they're resolved first
+        let mut filter = opts.filter.clone();
+        filter.teams = filter
+            .teams
+            .iter()
+            .map(|team| team::resolve(&teams, client.league(), team))
+            .map(|team| team.map(|team| team.abbreviation.clone()))
+            .collect::<Result<_, _>>()?;
+        games.retain(|game| game.matches(&filter));
 
         Ok(LazyStream {
             opts: opts.clone(),
+            client,
             games,
             teams,
         })
     }
 
-    pub fn date(&self) -> NaiveDate {
-        if self.opts.date.is_some() {
-            self.opts.date.clone().unwrap()
-        } else {
-            Local::today().naive_local()
-        }
+    /// Days games were fetched for
+    pub fn date_range(&self) -> DateRange {
+        self.opts.date_range()
+    }
+
+    /// League games are fetched from
+    pub fn league(&self) -> &dyn LeagueProvider {
+        self.client.league()
     }
 
     pub fn games(&self) -> Vec<Game> {
         self.games.clone()
     }
 
-    pub fn check_team_abbrev(&self, team_abbrev: &str) -> Result<(), Error> {
-        if self
-            .teams
+    /// Team of the league matching what was typed, see [`team::resolve`]
+    pub fn resolve_team(&self, team: &str) -> Result<Team, Error> {
+        team::resolve(&self.teams, self.league(), team).map(Team::clone)
+    }
+
+    /// Game of the team. If the team plays more than once in the date range, the game
+    /// that's on now is used, otherwise the next one to start, or the latest one when
+    /// they're all over. `game_number` picks a game of a doubleheader on that day instead
+    pub fn game_with_team_abbrev(
+        &self,
+        team_abbrev: &str,
+        game_number: Option<u32>,
+    ) -> Option<Game> {
+        let games = self.games_with_team_abbrev(team_abbrev);
+
+        // Once game 1 of a doubleheader is over, game 2 is the one that's next
+        let now = Utc::now();
+        let started = |game: &&Game| game.game_date <= now;
+        let game = games
             .iter()
-            .any(|team| team.abbreviation == team_abbrev)
-        {
-            Ok(())
-        } else {
-            bail!("Team abbreviation {} does not exist", team_abbrev);
+            .rev()
+            .find(|game| started(game) && game.ended_state().is_none())
+            .or_else(|| {
+                games
+                    .iter()
+                    .find(|game| !started(game) && game.ended_state().is_none())
+            })
+            .or_else(|| games.iter().rev().find(started))
+            .or_else(|| games.first())?;
+
+        match game_number {
+            Some(game_number) => games
+                .iter()
+                .find(|other| {
+                    other.selected_date == game.selected_date && other.game_number == game_number
+                })
+                .cloned(),
+            None => Some(game.clone()),
         }
     }
 
-    pub fn game_with_team_abbrev(&self, team_abbrev: &str) -> Option<Game> {
-        let game_idx = self.games.iter().position(|game| {
-            game.home_team.abbreviation == team_abbrev || game.away_team.abbreviation == team_abbrev
-        });
-        if let Some(index) = game_idx {
-            Some(self.games[index].clone())
-        } else {
-            None
+    /// Every game of the team on the day of the game picked by
+    /// [`LazyStream::game_with_team_abbrev`], so both games of a doubleheader
+    pub fn day_games_with_team_abbrev(&self, team_abbrev: &str) -> Vec<Game> {
+        match self.game_with_team_abbrev(team_abbrev, None) {
+            Some(game) => self
+                .games_with_team_abbrev(team_abbrev)
+                .into_iter()
+                .filter(|other| other.selected_date == game.selected_date)
+                .collect(),
+            None => vec![],
         }
     }
 
+    fn games_with_team_abbrev(&self, team_abbrev: &str) -> Vec<Game> {
+        self.games
+            .iter()
+            .filter(|game| {
+                game.home_team.abbreviation == team_abbrev
+                    || game.away_team.abbreviation == team_abbrev
+            })
+            .cloned()
+            .collect()
+    }
+
     #[allow(clippy::drop_ref)]
     pub async fn resolve_with_master_link(&mut self, cdn: Cdn) {
         let tasks: Vec<_> = self
@@ -113,48 +182,125 @@ impl LazyStream {
         future::join_all(tasks).await;
     }
 
+    /// Resolve the master links of the radio broadcasts of every game, see
+    /// [`LazyStream::resolve_with_master_link`]
     #[allow(clippy::drop_ref)]
-    pub async fn resolve_with_quality_link(&mut self, cdn: Cdn, quality: Quality) {
+    pub async fn resolve_audio_with_master_link(&mut self, cdn: Cdn) {
         let tasks: Vec<_> = self
             .games
             .iter_mut()
             .map(|game| async {
-                game.resolve_streams_quality_link(cdn, quality).await;
+                game.resolve_audio_streams_master_link(cdn).await;
                 drop(game);
             })
             .collect();
 
         future::join_all(tasks).await;
     }
+
+    #[allow(clippy::drop_ref)]
+    pub async fn resolve_with_quality_link(
+        &mut self,
+        cdn: Cdn,
+        quality: Quality,
+        fallback: QualityFallback,
+    ) {
+        let tasks: Vec<_> = self
+            .games
+            .iter_mut()
+            .map(|game| async {
+                game.resolve_streams_quality_link(cdn, quality, fallback)
+                    .await;
+                drop(game);
+            })
+            .collect();
+
+        future::join_all(tasks).await;
+    }
+}
+
+/// Mark the games of doubleheaders, numbering them in order of start time if the schedule
+/// doesn't say which game is which
+fn number_doubleheaders(games: &mut [Game]) {
+    for idx in 0..games.len() {
+        let previous = games[..idx]
+            .iter()
+            .filter(|other| other.is_same_matchup(&games[idx]))
+            .count();
+        let total = games
+            .iter()
+            .filter(|other| other.is_same_matchup(&games[idx]))
+            .count();
+
+        let game = &mut games[idx];
+        game.doubleheader = total > 1;
+        if game.doubleheader && game.game_number == 1 {
+            game.game_number = previous as u32 + 1;
+        }
+    }
+}
+
+/// Team of a scheduled game, built from the schedule if the league doesn't return it
+/// with its teams
+fn find_team(teams: &[Team], detail: &ScheduleGameTeamDetail) -> Team {
+    teams
+        .iter()
+        .find(|team| team.id == detail.id)
+        .cloned()
+        .unwrap_or_else(|| Team::from(detail))
 }
 
 #[derive(Clone)]
 pub struct Game {
-    sport: Sport,
+    client: Arc<Client>,
+    provider: Arc<dyn StreamProvider>,
     pub game_pk: u64,
     pub game_date: DateTime<Utc>,
+    pub status: Option<ScheduleGameStatus>,
+    /// Schedule `gameType` code, see [`GameType::from_code`]
+    pub game_type: String,
+    pub season: String,
+    /// Which game of a doubleheader this is, 1 for all other games
+    pub game_number: u32,
+    pub doubleheader: bool,
     pub selected_date: NaiveDate,
     pub streams: Option<BTreeMap<FeedType, Stream>>,
+    pub audio_streams: Option<Vec<Stream>>,
+    pub vod_streams: Option<Vec<Stream>>,
     pub home_team: Team,
     pub away_team: Team,
     pub game_content: Option<GameContentResponse>,
 }
 
 impl Game {
+    #[allow(clippy::too_many_arguments)]
     fn new(
-        sport: Sport,
+        client: Arc<Client>,
+        provider: Arc<dyn StreamProvider>,
         game_pk: u64,
         game_date: DateTime<Utc>,
+        status: Option<ScheduleGameStatus>,
+        game_type: String,
+        season: String,
+        game_number: Option<u32>,
         selected_date: NaiveDate,
         home_team: Team,
         away_team: Team,
     ) -> Self {
         Game {
-            sport,
+            client,
+            provider,
             game_pk,
             game_date,
+            status,
+            game_type,
+            season,
+            game_number: game_number.unwrap_or(1),
+            doubleheader: false,
             selected_date,
             streams: None,
+            audio_streams: None,
+            vod_streams: None,
             home_team,
             away_team,
             game_content: None,
@@ -164,31 +310,62 @@ impl Game {
     pub async fn streams(&mut self) -> Result<BTreeMap<FeedType, Stream>, Error> {
         if self.streams.is_none() {
             let mut streams = BTreeMap::new();
+            let mut audio_streams = vec![];
+            let mut vod_streams = vec![];
             let game_content = self.game_content().await?;
 
             if let Some(epg) = game_content.media.epg {
                 for epg in epg {
-                    if epg.title == "NHLTV" || epg.title == "MLBTV" {
-                        if let Some(items) = epg.items {
-                            for item in items {
-                                if let Some(feed_type) = item.media_feed_type {
-                                    let id = match self.sport {
-                                        Sport::Mlb => format!("{}", item.id.unwrap()),
-                                        Sport::Nhl => item.media_playback_id.unwrap(),
-                                    };
-
-                                    let feed_type = match FeedType::from_str(feed_type.as_str()) {
-                                        Ok(feed_type) => feed_type,
-                                        Err(_) => continue,
-                                    };
-
-                                    let stream = Stream::new(
-                                        id,
-                                        self.sport,
-                                        feed_type,
-                                        self.game_date,
-                                        self.selected_date,
-                                    );
+                    let kind = match self.client.league().stream_kind(&epg.title) {
+                        Some(kind) => kind,
+                        None => continue,
+                    };
+
+                    if let Some(items) = epg.items {
+                        for item in items {
+                            if kind.is_vod() {
+                                vod_streams.extend(self.vod_stream(kind, item));
+                                continue;
+                            }
+
+                            if let Some(feed_type) = item
+                                .media_feed_type
+                                .as_ref()
+                                .or_else(|| item.item_type.as_ref())
+                            {
+                                let id = self.client.league().stream_id(&item);
+                                let id = match id {
+                                    Some(id) => id,
+                                    None => continue,
+                                };
+
+                                let feed_type = match FeedType::from_str(feed_type) {
+                                    Ok(feed_type) => feed_type,
+                                    Err(_) => continue,
+                                };
+
+                                let media_state = item
+                                    .media_state
+                                    .as_deref()
+                                    .map_or(MediaState::Unknown, MediaState::from);
+
+                                let mut stream = Stream::new(
+                                    id,
+                                    self.client.shared_league(),
+                                    self.provider.clone(),
+                                    self.client.http(),
+                                    feed_type.clone(),
+                                    media_state,
+                                    item.call_letters.filter(|call| !call.is_empty()),
+                                    self.game_date,
+                                    self.selected_date,
+                                );
+
+                                if kind == StreamKind::Audio {
+                                    stream.kind = StreamKind::Audio;
+                                    stream.language = item.language;
+                                    audio_streams.push(stream);
+                                } else {
                                     streams.insert(feed_type, stream);
                                 }
                             }
@@ -197,16 +374,173 @@ impl Game {
                 }
             }
             self.streams = Some(streams.clone());
+            self.audio_streams = Some(audio_streams);
+            self.vod_streams = Some(vod_streams);
             Ok(streams)
         } else {
             Ok(self.streams.clone().unwrap())
         }
     }
 
+    /// Radio broadcasts of the game
+    pub async fn audio_streams(&mut self) -> Result<Vec<Stream>, Error> {
+        if self.audio_streams.is_none() {
+            self.streams().await?;
+        }
+
+        Ok(self.audio_streams.clone().unwrap_or_default())
+    }
+
+    /// Condensed game, recap and extended highlights of the game, once they've been published
+    pub async fn vod_streams(&mut self) -> Result<Vec<Stream>, Error> {
+        if self.vod_streams.is_none() {
+            self.streams().await?;
+        }
+
+        Ok(self.vod_streams.clone().unwrap_or_default())
+    }
+
+    /// VOD item of the game of `kind`, E.g. the condensed game
+    pub async fn vod_stream_of_kind(&mut self, kind: StreamKind) -> Result<Stream, Error> {
+        self.vod_streams()
+            .await?
+            .into_iter()
+            .find(|stream| stream.kind == kind)
+            .ok_or_else(|| LazyStreamError::NoStreams.into())
+    }
+
+    /// Build a VOD stream from an epg item, using its HLS playback
+    fn vod_stream(&self, kind: StreamKind, item: GameContentEpgItem) -> Option<Stream> {
+        let playback = item
+            .playbacks?
+            .into_iter()
+            .find(|playback| playback.url.ends_with(".m3u8"))?;
+
+        // VOD items aren't a broadcast feed, their feed type is their kind, E.g.
+        // `CONDENSED_GAME`, so they're never mistaken for one
+        let feed_type = FeedType::Other(kind.to_string().to_uppercase().replace(' ', "_"));
+        let mut stream = Stream::new(
+            item.id.map(|id| id.to_string()).unwrap_or_default(),
+            self.client.shared_league(),
+            self.provider.clone(),
+            self.client.http(),
+            feed_type,
+            MediaState::Unknown,
+            None,
+            self.game_date,
+            self.selected_date,
+        );
+        stream.kind = kind;
+        stream.playback_url = Some(playback.url);
+
+        Some(stream)
+    }
+
+    /// ` (Game 2)` for games of a doubleheader, empty for all other games
+    pub fn game_number_suffix(&self) -> String {
+        if self.doubleheader {
+            format!(" (Game {})", self.game_number)
+        } else {
+            String::new()
+        }
+    }
+
+    fn is_same_matchup(&self, other: &Game) -> bool {
+        self.selected_date == other.selected_date
+            && self.home_team.id == other.home_team.id
+            && self.away_team.id == other.away_team.id
+    }
+
+    /// Whether the game is in progress
+    pub fn is_live(&self) -> bool {
+        match &self.status {
+            Some(status) => status.abstract_game_state == "Live",
+            None => false,
+        }
+    }
+
+    /// Whether the game hasn't started yet
+    pub fn is_upcoming(&self) -> bool {
+        match &self.status {
+            Some(status) => status.abstract_game_state == "Preview",
+            None => self.game_date > Utc::now(),
+        }
+    }
+
+    /// Whether the game passes every filter
+    fn matches(&self, filter: &GameFilter) -> bool {
+        if !filter.game_types.is_empty() {
+            match GameType::from_code(&self.game_type) {
+                Some(game_type) if filter.game_types.contains(&game_type) => {}
+                _ => return false,
+            }
+        }
+
+        if let Some(season) = &filter.season {
+            if season != &self.season {
+                return false;
+            }
+        }
+
+        if !filter.teams.is_empty()
+            && !filter.teams.iter().any(|team| {
+                team.eq_ignore_ascii_case(&self.home_team.abbreviation)
+                    || team.eq_ignore_ascii_case(&self.away_team.abbreviation)
+            })
+        {
+            return false;
+        }
+
+        let start = self.game_date.with_timezone(&Local).time();
+        let in_window = match (filter.start_after, filter.start_before) {
+            (Some(after), Some(before)) if after > before => start >= after || start < before,
+            (after, before) => {
+                after.map_or(true, |after| start >= after)
+                    && before.map_or(true, |before| start < before)
+            }
+        };
+        if !in_window {
+            return false;
+        }
+
+        (!filter.live || self.is_live()) && (!filter.upcoming || self.is_upcoming())
+    }
+
+    /// Detailed state of the game if it's over or won't be played, E.g. Final or Postponed
+    pub fn ended_state(&self) -> Option<&str> {
+        let status = self.status.as_ref()?;
+
+        match status.detailed_state.as_str() {
+            "Postponed" | "Cancelled" | "Suspended" => Some(&status.detailed_state),
+            _ if status.abstract_game_state == "Final" => Some(&status.detailed_state),
+            _ => None,
+        }
+    }
+
+    /// Fetch the latest status of the game from the schedule, the cached one when offline
+    pub async fn refresh_status(&mut self) -> Result<(), Error> {
+        let schedule = self
+            .client
+            .get_latest_schedule_for(self.selected_date)
+            .await?;
+
+        if let Some(game) = schedule
+            .games
+            .into_iter()
+            .find(|game| game.game_pk == self.game_pk)
+        {
+            self.status = game.status;
+        }
+
+        Ok(())
+    }
+
     pub async fn game_content(&mut self) -> Result<GameContentResponse, Error> {
         if self.game_content.is_none() {
-            let client = Client::new(self.sport);
-            let game_content = client.get_game_content(self.game_pk).await?;
+            let game_content = self
+                .client
+                .get_game_content(self.game_pk, self.game_date)
+                .await?;
             self.game_content = Some(game_content.clone());
             Ok(game_content)
         } else {
@@ -255,28 +589,98 @@ impl Game {
             self.streams.clone().unwrap()
         };
 
-        let mut feed_type: FeedType = if let Some(feed_type) = feed_type {
+        let feed_type = match feed_type {
+            Some(feed_type) if streams.is_empty() || streams.contains_key(&feed_type) => feed_type,
+            Some(feed_type) => {
+                let available: Vec<_> = streams.keys().map(FeedType::as_str).collect();
+                return Err(LazyStreamError::FeedNotAvailable(
+                    feed_type.to_string(),
+                    available.join(", "),
+                )
+                .into());
+            }
+            None if self.home_team.abbreviation == team_abbrev => FeedType::Home,
+            None => FeedType::Away,
+        };
+
+        let feed_type = if streams.contains_key(&feed_type) {
             feed_type
-        } else if self.home_team.abbreviation == team_abbrev {
+        } else if streams.contains_key(&FeedType::National) {
+            FeedType::National
+        } else if streams.contains_key(&FeedType::Home) {
+            FeedType::Home
+        } else if streams.contains_key(&FeedType::Away) {
+            FeedType::Away
+        } else {
+            // Fall back to whatever feed the game offers, E.g. IN_MARKET_HOME
+            streams.keys().next().cloned().unwrap_or(feed_type)
+        };
+
+        if let Some(stream) = streams.remove(&feed_type) {
+            Ok(stream)
+        } else {
+            Err(LazyStreamError::NoStreams.into())
+        }
+    }
+
+    /// Radio broadcast for `feed_type`, which must be one of the game's broadcasts, defaulting
+    /// to the supplied team's applicable Home / Away broadcast. English broadcasts are
+    /// preferred over other languages
+    pub async fn audio_stream_with_feed_or_default(
+        &mut self,
+        feed_type: Option<FeedType>,
+        team_abbrev: &str,
+    ) -> Result<Stream, Error> {
+        let mut audio_streams = self.audio_streams().await?;
+        audio_streams.sort_by_key(|stream| !stream.is_english());
+
+        let default = if self.home_team.abbreviation == team_abbrev {
             FeedType::Home
         } else {
             FeedType::Away
         };
+        let idx = audio_streams
+            .iter()
+            .position(|stream| &stream.feed_type == feed_type.as_ref().unwrap_or(&default));
 
-        if !streams.contains_key(&feed_type) {
-            if streams.contains_key(&FeedType::National) {
-                feed_type = FeedType::National
-            } else if streams.contains_key(&FeedType::Home) {
-                feed_type = FeedType::Home
-            } else {
-                feed_type = FeedType::Away
+        match (idx, feed_type) {
+            (Some(idx), _) => Ok(audio_streams.remove(idx)),
+            (None, _) if audio_streams.is_empty() => Err(LazyStreamError::NoStreams.into()),
+            // Only the default broadcast falls back to another one, like the video streams
+            (None, Some(feed_type)) => {
+                let mut available: Vec<_> = audio_streams
+                    .iter()
+                    .map(|stream| stream.feed_type.as_str())
+                    .collect();
+                available.sort_unstable();
+                available.dedup();
+                Err(
+                    LazyStreamError::FeedNotAvailable(feed_type.to_string(), available.join(", "))
+                        .into(),
+                )
             }
+            (None, None) => Ok(audio_streams.remove(0)),
         }
+    }
 
-        if let Some(stream) = streams.remove(&feed_type) {
-            Ok(stream)
-        } else {
-            bail!("Couldn't find any ");
+    /// Stream of `kind` for the supplied team, see [`Game::stream_with_feed_or_default`] and
+    /// [`Game::audio_stream_with_feed_or_default`]
+    pub async fn stream_of_kind(
+        &mut self,
+        kind: StreamKind,
+        feed_type: Option<FeedType>,
+        team_abbrev: &str,
+    ) -> Result<Stream, Error> {
+        match kind {
+            StreamKind::Video => {
+                self.stream_with_feed_or_default(feed_type, team_abbrev)
+                    .await
+            }
+            StreamKind::Audio => {
+                self.audio_stream_with_feed_or_default(feed_type, team_abbrev)
+                    .await
+            }
+            kind => self.vod_stream_of_kind(kind).await,
         }
     }
 
@@ -292,9 +696,8 @@ impl Game {
 
         let tasks: Vec<_> = self
             .streams
-            .as_mut()
-            .unwrap()
             .iter_mut()
+            .flatten()
             .map(|(_, stream)| async {
                 stream.resolve_master_link(cdn).await;
                 drop(stream);
@@ -305,17 +708,40 @@ impl Game {
     }
 
     #[allow(clippy::drop_ref)]
-    async fn resolve_streams_quality_link(&mut self, cdn: Cdn, quality: Quality) {
+    async fn resolve_audio_streams_master_link(&mut self, cdn: Cdn) {
+        if self.audio_streams.is_none() {
+            self.resolve_streams().await;
+        }
+
+        let tasks: Vec<_> = self
+            .audio_streams
+            .iter_mut()
+            .flatten()
+            .map(|stream| async {
+                stream.resolve_master_link(cdn).await;
+                drop(stream);
+            })
+            .collect();
+
+        future::join_all(tasks).await;
+    }
+
+    #[allow(clippy::drop_ref)]
+    async fn resolve_streams_quality_link(
+        &mut self,
+        cdn: Cdn,
+        quality: Quality,
+        fallback: QualityFallback,
+    ) {
         if self.streams.is_none() {
             self.resolve_streams().await;
         }
         let tasks: Vec<_> = self
             .streams
-            .as_mut()
-            .unwrap()
             .iter_mut()
+            .flatten()
             .map(|(_, stream)| async {
-                stream.resolve_quality_link(cdn, quality).await;
+                stream.resolve_quality_link(cdn, quality, fallback).await;
                 drop(stream);
             })
             .collect();
@@ -324,192 +750,412 @@ impl Game {
     }
 }
 
+/// Kind of content a stream is, the broadcasts of the game or VOD items published after it
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum StreamKind {
+    Video,
+    Audio,
+    Condensed,
+    Recap,
+    Highlights,
+}
+
+impl StreamKind {
+    /// Whether the stream is a VOD item with its own playback link, rather than a broadcast
+    /// resolved through the stream provider
+    pub fn is_vod(self) -> bool {
+        match self {
+            StreamKind::Video | StreamKind::Audio => false,
+            StreamKind::Condensed | StreamKind::Recap | StreamKind::Highlights => true,
+        }
+    }
+}
+
+impl From<StreamKind> for &str {
+    fn from(kind: StreamKind) -> &'static str {
+        match kind {
+            StreamKind::Video => "Video",
+            StreamKind::Audio => "Radio",
+            StreamKind::Condensed => "Condensed Game",
+            StreamKind::Recap => "Recap",
+            StreamKind::Highlights => "Extended Highlights",
+        }
+    }
+}
+
+impl std::fmt::Display for StreamKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s: &str = (*self).into();
+        write!(f, "{}", s)
+    }
+}
+
+/// State of a stream's media, as reported by the game content epg
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MediaState {
+    Upcoming,
+    Live,
+    Archived,
+    Unknown,
+}
+
+impl From<&str> for MediaState {
+    fn from(s: &str) -> Self {
+        match s {
+            "MEDIA_OFF" => MediaState::Upcoming,
+            "MEDIA_ON" => MediaState::Live,
+            "MEDIA_ARCHIVE" => MediaState::Archived,
+            _ => MediaState::Unknown,
+        }
+    }
+}
+
+impl From<MediaState> for &str {
+    fn from(state: MediaState) -> &'static str {
+        match state {
+            MediaState::Upcoming => "Upcoming",
+            MediaState::Live => "Live",
+            MediaState::Archived => "Archived",
+            MediaState::Unknown => "Unknown",
+        }
+    }
+}
+
+impl std::fmt::Display for MediaState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s: &str = (*self).into();
+        write!(f, "{}", s)
+    }
+}
+
 #[derive(Clone)]
 #[allow(clippy::option_option)]
 pub struct Stream {
     id: String,
-    sport: Sport,
+    league: Arc<dyn LeagueProvider>,
+    provider: Arc<dyn StreamProvider>,
+    http: Arc<HttpContext>,
+    pub kind: StreamKind,
     pub feed_type: FeedType,
+    pub media_state: MediaState,
+    pub call_letters: Option<String>,
+    /// Language of a radio broadcast, E.g. `en` or `es`
+    pub language: Option<String>,
+    /// HLS link of a VOD item, which doesn't go through the stream provider
+    playback_url: Option<String>,
     game_date: DateTime<Utc>,
     selected_date: NaiveDate,
     master_link: Option<Option<String>>,
-    master_m3u8: Option<String>,
+    master_playlist: Option<MasterPlaylist>,
     quality_link: Option<Option<String>>,
+    quality_fallback: Option<String>,
+    cdn: Option<Cdn>,
+    failed_cdns: Vec<Cdn>,
 }
 
 impl Stream {
+    #[allow(clippy::too_many_arguments)]
     fn new(
         id: String,
-        sport: Sport,
+        league: Arc<dyn LeagueProvider>,
+        provider: Arc<dyn StreamProvider>,
+        http: Arc<HttpContext>,
         feed_type: FeedType,
+        media_state: MediaState,
+        call_letters: Option<String>,
         game_date: DateTime<Utc>,
         selected_date: NaiveDate,
     ) -> Self {
         Stream {
             id,
-            sport,
+            league,
+            provider,
+            http,
+            kind: StreamKind::Video,
             feed_type,
+            media_state,
+            call_letters,
+            language: None,
+            playback_url: None,
             game_date,
             selected_date,
             master_link: None,
-            master_m3u8: None,
+            master_playlist: None,
             quality_link: None,
+            quality_fallback: None,
+            cdn: None,
+            failed_cdns: vec![],
+        }
+    }
+
+    /// Feed type with the broadcaster's call letters, E.g. `HOME - SN`. Radio
+    /// broadcasts also get their language if it isn't English, E.g. `HOME - KWKW (es) Radio`.
+    /// VOD items are named after their kind, E.g. `Condensed Game`
+    pub fn feed_name(&self) -> String {
+        if self.kind.is_vod() {
+            return self.kind.to_string();
+        }
+
+        let mut name = match &self.call_letters {
+            Some(call_letters) => format!("{} - {}", self.feed_type, call_letters),
+            None => self.feed_type.to_string(),
+        };
+
+        if self.kind == StreamKind::Audio {
+            if let (false, Some(language)) = (self.is_english(), &self.language) {
+                name.push_str(&format!(" ({})", language));
+            }
+            name.push_str(" Radio");
+        }
+
+        name
+    }
+
+    fn is_english(&self) -> bool {
+        self.language
+            .as_deref()
+            .map_or(true, |language| language == "en")
+    }
+
+    /// Feed name with the media state, E.g. `HOME - SN [Live]`
+    pub fn label(&self) -> String {
+        match self.media_state {
+            MediaState::Unknown => self.feed_name(),
+            state => format!("{} [{}]", self.feed_name(), state),
         }
     }
 
+    /// Host link for the stream, using the CDN the stream was resolved on if it has been.
+    /// VOD items link straight to their playback
     pub fn host_link(&self, cdn: Cdn) -> String {
-        format!(
-            "{}/getM3U8.php?league={}&date={}&id={}&cdn={}",
-            HOST,
-            self.sport,
-            self.selected_date.format("%Y-%m-%d"),
-            self.id,
-            cdn,
-        )
+        if let Some(playback_url) = &self.playback_url {
+            return playback_url.clone();
+        }
+
+        self.host_link_for(self.cdn.unwrap_or(cdn))
+    }
+
+    fn host_link_for(&self, cdn: Cdn) -> String {
+        let cdn = if cdn == Cdn::Auto { Cdn::all()[0] } else { cdn };
+
+        self.provider
+            .host_link(self.league.as_ref(), self.selected_date, &self.id, cdn)
+    }
+
+    /// CDN the stream was resolved on
+    pub fn cdn(&self) -> Option<Cdn> {
+        self.cdn
     }
 
     pub async fn master_link(&mut self, cdn: Cdn) -> Result<String, Error> {
+        if let Some(playback_url) = &self.playback_url {
+            return Ok(playback_url.clone());
+        }
+
         if self.master_link.is_none() {
-            match get_master_link(&self.host_link(cdn)).await {
-                Ok(master_link) => {
+            match self.select_cdn(cdn).await {
+                Ok((cdn, master_link, master_playlist)) => {
+                    self.cdn = Some(cdn);
                     self.master_link = Some(Some(master_link.clone()));
+                    self.master_playlist = master_playlist;
                     Ok(master_link)
                 }
                 Err(e) => {
                     self.master_link = Some(None);
-                    bail!(e);
+                    Err(e)
                 }
             }
         } else if let Some(master_link) = self.master_link.clone().unwrap() {
             Ok(master_link)
         } else {
-            bail!("Master link is not avaialable");
+            Err(LazyStreamError::StreamNotAvailable.into())
+        }
+    }
+
+    pub async fn master_playlist(&mut self, cdn: Cdn) -> Result<MasterPlaylist, Error> {
+        if let Some(master_playlist) = &self.master_playlist {
+            return Ok(master_playlist.clone());
         }
+
+        let master_link = self
+            .master_link(cdn)
+            .await
+            .context("Master link not available yet")?;
+        let master_m3u8 = get_master_m3u8(&self.http, &master_link).await?;
+        let master_playlist = MasterPlaylist::parse(&master_link, &master_m3u8)
+            .context(LazyStreamError::InvalidPlaylist)?;
+
+        self.master_playlist = Some(master_playlist.clone());
+        Ok(master_playlist)
     }
 
-    pub async fn quality_link(&mut self, cdn: Cdn, quality: Quality) -> Result<String, Error> {
+    /// Link to the variant matching `quality`. If there isn't one, `fallback` decides which
+    /// link is used instead, see [`Stream::quality_fallback`]
+    pub async fn quality_link(
+        &mut self,
+        cdn: Cdn,
+        quality: Quality,
+        fallback: QualityFallback,
+    ) -> Result<String, Error> {
         if self.quality_link.is_none() {
-            if self.master_m3u8.is_none() {
-                if let Ok(master_link) = self.master_link(cdn).await {
-                    match get_master_m3u8(&master_link).await {
-                        Err(e) => {
-                            self.quality_link = Some(None);
-                            bail!(e);
-                        }
-                        Ok(master_m3u8) => {
-                            self.master_m3u8 = Some(master_m3u8);
-                        }
-                    }
-                } else {
+            let master_playlist = match self.master_playlist(cdn).await {
+                Ok(master_playlist) => master_playlist,
+                Err(e) => {
                     self.quality_link = Some(None);
-                    bail!("Master link not available yet");
+                    return Err(e);
                 }
-            }
-            let master_link = self.master_link.as_ref().unwrap().as_ref().unwrap();
-            let master_m3u8 = self.master_m3u8.as_ref().unwrap();
+            };
 
-            if let Ok(quality_link) = get_quality_link(master_link, master_m3u8, quality) {
+            if let Ok(quality_link) = get_quality_link(&master_playlist, quality) {
                 self.quality_link = Some(Some(quality_link.clone()));
                 Ok(quality_link)
-            } else {
+            } else if fallback == QualityFallback::None {
                 self.quality_link = Some(None);
-                bail!("Link doesn't exist for specified quality");
+                Err(LazyStreamError::QualityNotAvailable.into())
+            } else {
+                let (quality_link, rendition) =
+                    match quality.fallback(&master_playlist.variants, fallback) {
+                        Some(variant) => (variant.uri.clone(), variant.name()),
+                        None => (self.master_link(cdn).await?, String::from("adaptive")),
+                    };
+
+                self.quality_link = Some(Some(quality_link.clone()));
+                self.quality_fallback = Some(rendition);
+                Ok(quality_link)
             }
         } else if let Some(quality_link) = self.quality_link.clone().unwrap() {
             Ok(quality_link)
         } else {
-            bail!("Could not get master m3u8 to build quality link");
+            Err(LazyStreamError::StreamNotAvailable.into())
         }
     }
 
-    async fn resolve_master_link(&mut self, cdn: Cdn) {
-        let _ = self.master_link(cdn).await;
-    }
-
-    async fn resolve_quality_link(&mut self, cdn: Cdn, quality: Quality) {
-        let _ = self.quality_link(cdn, quality).await;
+    /// Rendition used instead of the requested quality, if a fallback was needed
+    pub fn quality_fallback(&self) -> Option<&str> {
+        self.quality_fallback.as_deref()
     }
-}
 
-async fn get_master_link(url: &str) -> Result<String, Error> {
-    let uri = url.parse::<http::Uri>().context("Failed to build URI")?;
-    let request = http::Request::builder()
-        .method("GET")
-        .uri(uri)
-        .body(Body::empty())
-        .unwrap();
+    /// Mark the CDN the stream was resolved on as failed, so links are resolved on
+    /// another CDN next time. Returns `false` if there are no CDNs left to fail over to
+    pub fn failover(&mut self) -> bool {
+        // VOD items are only available from their own playback link
+        if self.playback_url.is_some() {
+            return false;
+        }
 
-    let client = NativeClient::default();
-    let resp = client.send(request).await?;
+        if let Some(cdn) = self.cdn.take() {
+            self.failed_cdns.push(cdn);
+        }
+        self.clear_links();
 
-    let mut body = resp.into_body();
-    let mut body_text = String::new();
-    body.read_to_string(&mut body_text)
-        .await
-        .context("Failed to read response body text")?;
+        Cdn::all().iter().any(|cdn| !self.failed_cdns.contains(cdn))
+    }
 
-    if !&body_text[..].starts_with("https") {
-        bail!("Stream not available yet");
+    /// Clear all resolved links so they're resolved again on next use
+    pub fn clear_links(&mut self) {
+        self.master_link = None;
+        self.master_playlist = None;
+        self.quality_link = None;
+        self.quality_fallback = None;
     }
 
-    Ok(body_text)
-}
+    /// Use `cdn` if the stream resolves on it, otherwise probe all CDNs and use
+    /// the fastest healthy one. CDNs that have been failed over are skipped
+    async fn select_cdn(&self, cdn: Cdn) -> Result<(Cdn, String, Option<MasterPlaylist>), Error> {
+        if cdn != Cdn::Auto && !self.failed_cdns.contains(&cdn) {
+            let host_link = self.host_link_for(cdn);
+            if let Ok(master_link) =
+                get_master_link(&self.http, self.provider.as_ref(), &host_link).await
+            {
+                return Ok((cdn, master_link, None));
+            }
+        }
+
+        let probes: Vec<_> = Cdn::all()
+            .iter()
+            .copied()
+            .filter(|probe| *probe != cdn && !self.failed_cdns.contains(probe))
+            .map(|probe| async move {
+                let start = Instant::now();
+                let result = probe_cdn(
+                    &self.http,
+                    self.provider.as_ref(),
+                    &self.host_link_for(probe),
+                )
+                .await;
+                (probe, result, start.elapsed())
+            })
+            .collect();
 
-async fn get_master_m3u8(url: &str) -> Result<String, Error> {
-    let uri = url.parse::<http::Uri>().context("Failed to build URI")?;
-    let request = http::Request::builder()
-        .method("GET")
-        .uri(uri)
-        .body(Body::empty())
-        .unwrap();
+        let mut results = future::join_all(probes).await;
+        results.sort_by_key(|(_, _, elapsed)| *elapsed);
 
-    let client = NativeClient::default();
-    let resp = client.send(request).await?;
+        let mut error = None;
+        for (cdn, result, _) in results {
+            match result {
+                Ok((master_link, master_playlist)) => {
+                    return Ok((cdn, master_link, Some(master_playlist)))
+                }
+                Err(e) => error = error.or(Some(e)),
+            }
+        }
 
-    let mut body = resp.into_body();
-    let mut body_text = String::new();
-    body.read_to_string(&mut body_text)
-        .await
-        .context("Failed to read response body text")?;
+        Err(error.unwrap_or_else(|| LazyStreamError::NoCdnAvailable.into()))
+    }
 
-    if body_text[..].starts_with("#EXTM3U") {
-        return Ok(body_text);
+    async fn resolve_master_link(&mut self, cdn: Cdn) {
+        let _ = self.master_link(cdn).await;
     }
 
-    bail!("Failed to get master m3u8");
+    async fn resolve_quality_link(
+        &mut self,
+        cdn: Cdn,
+        quality: Quality,
+        fallback: QualityFallback,
+    ) {
+        let _ = self.quality_link(cdn, quality, fallback).await;
+    }
 }
 
-fn get_quality_link(
-    master_link: &str,
-    master_m3u8: &str,
-    quality: Quality,
-) -> Result<String, Error> {
-    let quality_str: &str = quality.clone().into();
-    let quality_check = format!("x{}", quality_str);
+/// Resolve the master link on a CDN and fetch the master m3u8 from it, so a
+/// probe covers the whole path to the CDN
+async fn probe_cdn(
+    http: &HttpContext,
+    provider: &dyn StreamProvider,
+    host_link: &str,
+) -> Result<(String, MasterPlaylist), Error> {
+    let master_link = get_master_link(http, provider, host_link).await?;
+    let master_m3u8 = get_master_m3u8(http, &master_link).await?;
+    let master_playlist = MasterPlaylist::parse(&master_link, &master_m3u8)?;
 
-    let mut quality_idx = None;
-    for (idx, line) in master_m3u8.lines().enumerate() {
-        if (quality == Quality::_720p60 && line.contains("FRAME-RATE"))
-            || (quality != Quality::_720p60 && line.contains(&quality_check))
-        {
-            quality_idx = Some(idx + 1);
-            break;
-        }
-    }
+    Ok((master_link, master_playlist))
+}
 
-    if let Some(idx) = quality_idx {
-        let quality_line = master_m3u8
-            .lines()
-            .nth(idx)
-            .ok_or_else(|| format_err!("No stream found matching quality specified"))?;
+async fn get_master_link(
+    http: &HttpContext,
+    provider: &dyn StreamProvider,
+    url: &str,
+) -> Result<String, Error> {
+    let body_text = http.get_text(url).await?;
+
+    provider
+        .parse_master_link(&body_text)
+        .ok_or_else(|| LazyStreamError::StreamNotAvailable.into())
+}
 
-        let master_link_parts = master_link.rsplitn(2, '/').collect::<Vec<&str>>();
-        if master_link_parts.len() == 2 {
-            let quality_link = format!("{}/{}", master_link_parts[1], quality_line);
+async fn get_master_m3u8(http: &HttpContext, url: &str) -> Result<String, Error> {
+    let body_text = http.get_text(url).await?;
 
-            return Ok(quality_link);
-        }
+    if body_text[..].starts_with("#EXTM3U") {
+        return Ok(body_text);
     }
 
-    bail!("No stream found matching quality specified");
+    Err(LazyStreamError::InvalidPlaylist.into())
+}
+
+fn get_quality_link(master_playlist: &MasterPlaylist, quality: Quality) -> Result<String, Error> {
+    quality
+        .select(&master_playlist.variants)
+        .map(|variant| variant.uri.clone())
+        .ok_or_else(|| LazyStreamError::QualityNotAvailable.into())
 }
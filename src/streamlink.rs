@@ -1,23 +1,34 @@
 use crate::{
-    log_error,
-    opt::{CastCommand, Command, Opt, PlayCommand, Quality, RecordCommand},
-    stream::{Game, LazyStream, Stream},
+    browse::Action,
+    error::LazyStreamError,
+    exit_with_error, log_error,
+    net::jitter,
+    opt::{CastCommand, Cdn, Command, FeedType, Opt, PlayCommand, Quality, RecordCommand},
+    stream::{Game, LazyStream, Stream, StreamKind},
 };
-use async_std::{process, task};
-use chrono::Local;
-use failure::{bail, format_err, Error, ResultExt};
+use async_std::task;
+use chrono::{DateTime, Local, Utc};
+use failure::{bail, Error, ResultExt};
 use http::Uri;
 use mdns::RecordKind;
 use read_input::prelude::*;
 use std::{
-    collections::HashMap, io::Write, net::Ipv4Addr, path::PathBuf, process::Stdio, time::Duration,
+    collections::HashMap,
+    io::Write,
+    net::Ipv4Addr,
+    path::PathBuf,
+    process::Stdio,
+    time::{Duration, Instant},
 };
 
+/// Hours after the start of a game to stop waiting for its stream
+const GIVE_UP_HOURS: i64 = 8;
+
 pub fn run(opts: Opt) {
+    let error_format = opts.error_format;
     task::block_on(async {
         if let Err(e) = process(opts).await {
-            log_error(&e);
-            process::exit(1);
+            exit_with_error(&e, error_format);
         };
     });
 }
@@ -25,52 +36,252 @@ pub fn run(opts: Opt) {
 async fn process(opts: Opt) -> Result<(), Error> {
     task::spawn_blocking(check_streamlink)
         .await
-        .context(format_err!(
-            "Could not find and run Streamlink. Please ensure it is installed \
-             and accessible from your PATH"
-        ))?;
+        .context(LazyStreamError::MissingDependency("Streamlink"))?;
 
-    let (game, mut stream, command, restart, proxy, offset, quality) = match &opts.command {
+    let (games, command, restart, proxy, offset, quality) = match &opts.command {
         Command::Play { command } => process_play(&opts, command).await?,
         Command::Record { command } => process_record(&opts, command).await?,
         Command::Cast { command } => process_cast(&opts, command).await?,
         _ => bail!("Wrong command for module"),
     };
 
-    println!();
-    while stream.master_link(opts.cdn).await.is_err() {
-        println!("Stream not available yet, will check again soon...");
-        task::sleep(Duration::from_secs(60 * 30)).await;
+    let total = games.len();
+    for (idx, (game, stream)) in games.into_iter().enumerate() {
+        if idx > 0 {
+            println!(
+                "\nMoving on to game {} of the doubleheader",
+                game.game_number
+            );
+        }
+
+        let result = process_game(
+            &opts, game, stream, &command, restart, &proxy, &offset, quality,
+        )
+        .await;
+        match result {
+            // A game of a doubleheader failing shouldn't keep the next one from streaming
+            Err(e) if idx + 1 < total => log_error(&e),
+            result => result?,
+        }
     }
-    let link = if let Some(quality) = quality {
-        stream.quality_link(opts.cdn, quality).await?
-    } else {
-        stream.master_link(opts.cdn).await?
+
+    Ok(())
+}
+
+/// Play, record or cast the stream picked in the game browser
+pub async fn process_browsed(
+    opts: &Opt,
+    action: Action,
+    game: Game,
+    stream: Stream,
+) -> Result<(), Error> {
+    let (output, cast_host, custom_player) = match &opts.command {
+        Command::Browse {
+            output,
+            cast_host,
+            custom_player,
+        } => (output, cast_host, custom_player),
+        _ => bail!("Wrong command for module"),
     };
 
-    let args = StreamlinkArgs {
-        link,
+    task::spawn_blocking(check_streamlink)
+        .await
+        .context(LazyStreamError::MissingDependency("Streamlink"))?;
+
+    let command = match action {
+        Action::Play => StreamlinkCommand::Play {
+            passthrough: false,
+            custom_player: custom_player.clone(),
+        },
+        Action::Record => StreamlinkCommand::Record {
+            output: check_output(Some(output))?,
+            audio_source: None,
+        },
+        Action::Cast => {
+            task::spawn_blocking(check_vlc)
+                .await
+                .context(LazyStreamError::MissingDependency("VLC"))?;
+
+            let cast_host = cast_host_or_search(cast_host.as_deref()).await?;
+            println!("\nUsing cast device {}\n", cast_host);
+
+            StreamlinkCommand::cast_with_ip(cast_host, None)
+        }
+    };
+
+    process_game(
+        opts,
         game,
         stream,
-        command,
-        restart,
-        proxy,
-        offset,
-        quality,
+        &command,
+        false,
+        &None,
+        &None,
+        opts.quality,
+    )
+    .await
+}
+
+/// Wait for the stream of a game and pass it to Streamlink, failing over to the next CDN
+/// if Streamlink fails
+#[allow(clippy::too_many_arguments)]
+async fn process_game(
+    opts: &Opt,
+    mut game: Game,
+    mut stream: Stream,
+    command: &StreamlinkCommand,
+    restart: bool,
+    proxy: &Option<Uri>,
+    offset: &Option<String>,
+    quality: Option<Quality>,
+) -> Result<(), Error> {
+    // Radio broadcasts only have audio renditions, so there's no quality to pick
+    let quality = if stream.kind == StreamKind::Audio {
+        None
+    } else {
+        quality
     };
 
-    task::spawn_blocking(move || streamlink(args)).await?;
+    println!();
+    wait_for_stream(&mut game, &mut stream, opts.cdn).await?;
+
+    let mut failed_over = false;
+    loop {
+        let link = if let Some(quality) = quality {
+            let quality_link = stream
+                .quality_link(opts.cdn, quality, opts.quality_fallback)
+                .await?;
+            if let Some(rendition) = stream.quality_fallback() {
+                println!("Quality {} not available, using {}", quality, rendition);
+            }
+            quality_link
+        } else {
+            stream.master_link(opts.cdn).await?
+        };
+
+        let cdn = stream.cdn().unwrap_or(opts.cdn);
+        if !stream.kind.is_vod() {
+            println!("Using CDN {}", cdn);
+        }
+
+        if let Some(audio_source) = command.audio_source() {
+            check_audio_source(&mut stream, opts.cdn, audio_source).await;
+        }
+
+        let args = StreamlinkArgs {
+            link,
+            game: game.clone(),
+            stream: stream.clone(),
+            command: command.clone(),
+            restart,
+            proxy: proxy.clone(),
+            offset: offset.clone(),
+            quality,
+            failed_over,
+        };
+
+        match task::spawn_blocking(move || streamlink(args)).await {
+            Ok(()) => return Ok(()),
+            Err(e) if stream.failover() => {
+                log_error(&e);
+                println!("\nCDN {} failed, failing over to the next CDN...", cdn);
+                failed_over = true;
+            }
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+/// Wait until the stream is available. Checks are rare hours before the game and
+/// frequent around the start of it. Gives up once the game is over or won't be played
+async fn wait_for_stream(game: &mut Game, stream: &mut Stream, cdn: Cdn) -> Result<(), Error> {
+    while stream.master_link(cdn).await.is_err() {
+        let _ = game.refresh_status().await;
+        if let Some(state) = game.ended_state() {
+            return Err(LazyStreamError::GameEnded(state.to_owned()).into());
+        }
+
+        let until_start = game.game_date.signed_duration_since(Utc::now());
+        if until_start < -chrono::Duration::hours(GIVE_UP_HOURS) {
+            return Err(LazyStreamError::StreamTimedOut(GIVE_UP_HOURS).into());
+        }
+
+        countdown(poll_interval(until_start), game.game_date).await;
+        stream.clear_links();
+    }
 
     Ok(())
 }
 
+/// How long to wait before checking for a stream again
+fn poll_interval(until_start: chrono::Duration) -> Duration {
+    let minutes = until_start.num_minutes();
+
+    let interval = if minutes > 180 {
+        Duration::from_secs(30 * 60)
+    } else if minutes > 60 {
+        Duration::from_secs(10 * 60)
+    } else if minutes > 15 {
+        Duration::from_secs(3 * 60)
+    } else if minutes > -60 {
+        Duration::from_secs(30)
+    } else {
+        Duration::from_secs(2 * 60)
+    };
+
+    interval * 9 / 10 + jitter(interval / 5)
+}
+
+/// Show a countdown to the next check on a single terminal line
+async fn countdown(wait: Duration, game_date: DateTime<Utc>) {
+    let next_check = Instant::now() + wait;
+
+    loop {
+        let remaining = next_check.saturating_duration_since(Instant::now());
+        if remaining == Duration::from_secs(0) {
+            break;
+        }
+
+        let until_start = game_date.signed_duration_since(Utc::now());
+        let game_time = if until_start > chrono::Duration::zero() {
+            format!("Game starts in {}", format_duration(until_start))
+        } else {
+            format!("Game started {} ago", format_duration(-until_start))
+        };
+
+        print!(
+            "\r{}, stream not available yet. Checking again in {}   ",
+            game_time,
+            format_duration(
+                chrono::Duration::from_std(remaining).unwrap_or_else(|_| chrono::Duration::zero())
+            )
+        );
+        let _ = std::io::stdout().flush();
+
+        task::sleep(remaining.min(Duration::from_secs(1))).await;
+    }
+
+    println!();
+}
+
+/// Format a duration as `[Hh ]MM:SS`
+fn format_duration(duration: chrono::Duration) -> String {
+    let seconds = duration.num_seconds().max(0);
+    let (hours, minutes, seconds) = (seconds / 3600, seconds / 60 % 60, seconds % 60);
+
+    if hours > 0 {
+        format!("{}h {:02}:{:02}", hours, minutes, seconds)
+    } else {
+        format!("{:02}:{:02}", minutes, seconds)
+    }
+}
+
 async fn process_play(
     opts: &Opt,
     command: &PlayCommand,
 ) -> Result<
     (
-        Game,
-        Stream,
+        Vec<(Game, Stream)>,
         StreamlinkCommand,
         bool,
         Option<Uri>,
@@ -81,17 +292,17 @@ async fn process_play(
 > {
     match command {
         PlayCommand::Select {
+            selection,
             restart,
             proxy,
             offset,
             ..
         } => {
-            let (game, stream) = crate::select::process(opts, true).await?;
+            let (game, stream) = crate::select::process(opts, selection, true).await?;
 
             let streamlink_command = StreamlinkCommand::from(command);
             Ok((
-                game,
-                stream,
+                vec![(game, stream)],
                 streamlink_command,
                 *restart,
                 proxy.clone(),
@@ -103,35 +314,36 @@ async fn process_play(
             team_abbrev,
             restart,
             feed_type,
+            game_number,
+            doubleheader,
+            radio,
+            condensed,
+            recap,
+            highlights,
             proxy,
             offset,
             ..
         } => {
-            let lazy_stream = LazyStream::new(opts).await?;
-            lazy_stream.check_team_abbrev(&team_abbrev)?;
-            println!("Found matching team for {}", team_abbrev);
-
-            if let Some(mut game) = lazy_stream.game_with_team_abbrev(&team_abbrev) {
-                println!("Game found for today");
-
-                let stream = game
-                    .stream_with_feed_or_default(*feed_type, team_abbrev)
-                    .await?;
-                println!("Using stream feed {}", stream.feed_type);
-
-                let streamlink_command = StreamlinkCommand::from(command);
-                Ok((
-                    game,
-                    stream,
-                    streamlink_command,
-                    *restart,
-                    proxy.clone(),
-                    offset.clone(),
-                    opts.quality,
-                ))
-            } else {
-                bail!("There are no games today for {}", team_abbrev);
-            }
+            let kind = stream_kind(*radio, *condensed, *recap, *highlights);
+            let games = team_games(
+                opts,
+                team_abbrev,
+                *game_number,
+                *doubleheader,
+                kind,
+                feed_type.clone(),
+            )
+            .await?;
+
+            let streamlink_command = StreamlinkCommand::from(command);
+            Ok((
+                games,
+                streamlink_command,
+                *restart,
+                proxy.clone(),
+                offset.clone(),
+                opts.quality,
+            ))
         }
     }
 }
@@ -141,8 +353,7 @@ async fn process_record(
     command: &RecordCommand,
 ) -> Result<
     (
-        Game,
-        Stream,
+        Vec<(Game, Stream)>,
         StreamlinkCommand,
         bool,
         Option<Uri>,
@@ -153,19 +364,22 @@ async fn process_record(
 > {
     match command {
         RecordCommand::Select {
+            selection,
             output,
             restart,
             proxy,
             offset,
-            ..
+            audio_source,
         } => {
-            check_output(&output)?;
-            let (game, stream) = crate::select::process(opts, true).await?;
+            let output = check_output(output.as_ref())?;
+            let (game, stream) = crate::select::process(opts, selection, true).await?;
 
-            let streamlink_command = StreamlinkCommand::from(command);
+            let streamlink_command = StreamlinkCommand::Record {
+                output,
+                audio_source: audio_source.clone(),
+            };
             Ok((
-                game,
-                stream,
+                vec![(game, stream)],
                 streamlink_command,
                 *restart,
                 proxy.clone(),
@@ -177,38 +391,42 @@ async fn process_record(
             team_abbrev,
             restart,
             feed_type,
+            game_number,
+            doubleheader,
+            radio,
+            condensed,
+            recap,
+            highlights,
             output,
             proxy,
             offset,
-            ..
+            audio_source,
         } => {
-            check_output(&output)?;
-
-            let lazy_stream = LazyStream::new(opts).await?;
-            lazy_stream.check_team_abbrev(&team_abbrev)?;
-            println!("Found matching team for {}", team_abbrev);
-
-            if let Some(mut game) = lazy_stream.game_with_team_abbrev(&team_abbrev) {
-                println!("Game found for today");
-
-                let stream = game
-                    .stream_with_feed_or_default(*feed_type, team_abbrev)
-                    .await?;
-                println!("Using stream feed {}", stream.feed_type);
-
-                let streamlink_command = StreamlinkCommand::from(command);
-                Ok((
-                    game,
-                    stream,
-                    streamlink_command,
-                    *restart,
-                    proxy.clone(),
-                    offset.clone(),
-                    opts.quality,
-                ))
-            } else {
-                bail!("There are no games today for {}", team_abbrev);
-            }
+            let output = check_output(output.as_ref())?;
+
+            let kind = stream_kind(*radio, *condensed, *recap, *highlights);
+            let games = team_games(
+                opts,
+                team_abbrev,
+                *game_number,
+                *doubleheader,
+                kind,
+                feed_type.clone(),
+            )
+            .await?;
+
+            let streamlink_command = StreamlinkCommand::Record {
+                output,
+                audio_source: audio_source.clone(),
+            };
+            Ok((
+                games,
+                streamlink_command,
+                *restart,
+                proxy.clone(),
+                offset.clone(),
+                opts.quality,
+            ))
         }
     }
 }
@@ -218,8 +436,7 @@ async fn process_cast(
     command: &CastCommand,
 ) -> Result<
     (
-        Game,
-        Stream,
+        Vec<(Game, Stream)>,
         StreamlinkCommand,
         bool,
         Option<Uri>,
@@ -228,36 +445,28 @@ async fn process_cast(
     ),
     Error,
 > {
-    task::spawn_blocking(check_vlc).await.context(format_err!(
-        "Could not find and run VLC. Please ensure it is installed \
-         and accessible from your PATH"
-    ))?;
+    task::spawn_blocking(check_vlc)
+        .await
+        .context(LazyStreamError::MissingDependency("VLC"))?;
 
     match command {
         CastCommand::Select {
+            selection,
             restart,
             proxy,
             offset,
             audio_source,
         } => {
-            let (game, stream) = crate::select::process(opts, true).await?;
+            let (game, stream) = crate::select::process(opts, selection, true).await?;
 
-            let cast_devices = task::spawn_blocking(|| {
-                print!("\nSearching for cast devices...");
-                let _ = std::io::stdout().flush();
-                find_cast_devices()
-            })
-            .await?;
-
-            let cast_ip = select_cast_device(cast_devices)?;
-            println!("\nUsing cast device {}\n", cast_ip);
+            let cast_host = cast_host_or_search(None).await?;
+            println!("\nUsing cast device {}\n", cast_host);
 
             let streamlink_command =
-                StreamlinkCommand::cast_with_ip(cast_ip.to_string(), audio_source.clone());
+                StreamlinkCommand::cast_with_ip(cast_host, audio_source.clone());
 
             Ok((
-                game,
-                stream,
+                vec![(game, stream)],
                 streamlink_command,
                 *restart,
                 proxy.clone(),
@@ -267,42 +476,109 @@ async fn process_cast(
         }
         CastCommand::Team {
             team_abbrev,
+            cast_host,
             restart,
             feed_type,
+            game_number,
+            doubleheader,
             proxy,
             offset,
-            ..
+            audio_source,
         } => {
-            let lazy_stream = LazyStream::new(opts).await?;
-            lazy_stream.check_team_abbrev(&team_abbrev)?;
-            println!("Found matching team for {}", team_abbrev);
-
-            if let Some(mut game) = lazy_stream.game_with_team_abbrev(&team_abbrev) {
-                println!("Game found for today");
-
-                let stream = game
-                    .stream_with_feed_or_default(*feed_type, team_abbrev)
-                    .await?;
-                println!("Using stream feed {}", stream.feed_type);
-
-                let streamlink_command = StreamlinkCommand::from(command);
-                Ok((
-                    game,
-                    stream,
-                    streamlink_command,
-                    *restart,
-                    proxy.clone(),
-                    offset.clone(),
-                    opts.quality,
-                ))
-            } else {
-                bail!("There are no games today for {}", team_abbrev);
-            }
+            let kind = StreamKind::Video;
+            let games = team_games(
+                opts,
+                team_abbrev,
+                *game_number,
+                *doubleheader,
+                kind,
+                feed_type.clone(),
+            )
+            .await?;
+
+            let cast_host = cast_host_or_search(cast_host.as_deref()).await?;
+            let streamlink_command =
+                StreamlinkCommand::cast_with_ip(cast_host, audio_source.clone());
+            Ok((
+                games,
+                streamlink_command,
+                *restart,
+                proxy.clone(),
+                offset.clone(),
+                opts.quality,
+            ))
         }
     }
 }
 
-#[derive(PartialEq)]
+/// Games of the team with the stream of each to use. Both games of a doubleheader if
+/// `doubleheader` is set
+async fn team_games(
+    opts: &Opt,
+    team: &str,
+    game_number: Option<u32>,
+    doubleheader: bool,
+    kind: StreamKind,
+    feed_type: Option<FeedType>,
+) -> Result<Vec<(Game, Stream)>, Error> {
+    let lazy_stream = LazyStream::new(opts).await?;
+    let team = lazy_stream.resolve_team(team)?;
+    println!("Found matching team {} ({})", team.name, team.abbreviation);
+    let team_abbrev = team.abbreviation.as_str();
+
+    let games = if doubleheader {
+        lazy_stream.day_games_with_team_abbrev(team_abbrev)
+    } else {
+        lazy_stream
+            .game_with_team_abbrev(team_abbrev, game_number)
+            .into_iter()
+            .collect()
+    };
+
+    if games.is_empty() {
+        let missing = match game_number {
+            Some(game_number) => format!("{} (Game {})", team_abbrev, game_number),
+            None => team_abbrev.to_owned(),
+        };
+        let date = opts.date_range().to_string();
+        return Err(LazyStreamError::NoGame(missing, date).into());
+    }
+
+    let mut team_games = vec![];
+    for mut game in games {
+        println!(
+            "Game found for {}{}",
+            game.selected_date.format("%Y-%m-%d"),
+            game.game_number_suffix()
+        );
+
+        let stream = game
+            .stream_of_kind(kind, feed_type.clone(), team_abbrev)
+            .await?;
+        println!("Using stream feed {}", stream.label());
+
+        team_games.push((game, stream));
+    }
+
+    Ok(team_games)
+}
+
+/// Kind of stream the team commands should use
+fn stream_kind(radio: bool, condensed: bool, recap: bool, highlights: bool) -> StreamKind {
+    if radio {
+        StreamKind::Audio
+    } else if condensed {
+        StreamKind::Condensed
+    } else if recap {
+        StreamKind::Recap
+    } else if highlights {
+        StreamKind::Highlights
+    } else {
+        StreamKind::Video
+    }
+}
+
+#[derive(PartialEq, Clone)]
 enum StreamlinkCommand {
     Play {
         passthrough: bool,
@@ -325,6 +601,14 @@ impl StreamlinkCommand {
             audio_source,
         }
     }
+
+    fn audio_source(&self) -> Option<&str> {
+        match self {
+            StreamlinkCommand::Play { .. } => None,
+            StreamlinkCommand::Record { audio_source, .. } => audio_source.as_deref(),
+            StreamlinkCommand::Cast { audio_source, .. } => audio_source.as_deref(),
+        }
+    }
 }
 
 impl From<&PlayCommand> for StreamlinkCommand {
@@ -350,48 +634,6 @@ impl From<&PlayCommand> for StreamlinkCommand {
     }
 }
 
-impl From<&RecordCommand> for StreamlinkCommand {
-    fn from(cmd: &RecordCommand) -> Self {
-        match cmd {
-            RecordCommand::Select {
-                output,
-                audio_source,
-                ..
-            } => StreamlinkCommand::Record {
-                output: output.clone(),
-                audio_source: audio_source.clone(),
-            },
-            RecordCommand::Team {
-                output,
-                audio_source,
-                ..
-            } => StreamlinkCommand::Record {
-                output: output.clone(),
-                audio_source: audio_source.clone(),
-            },
-        }
-    }
-}
-
-impl From<&CastCommand> for StreamlinkCommand {
-    fn from(cmd: &CastCommand) -> Self {
-        match cmd {
-            CastCommand::Select { audio_source, .. } => StreamlinkCommand::Cast {
-                cast_host: "0.0.0.0".to_owned(),
-                audio_source: audio_source.clone(),
-            },
-            CastCommand::Team {
-                cast_host,
-                audio_source,
-                ..
-            } => StreamlinkCommand::Cast {
-                cast_host: cast_host.clone(),
-                audio_source: audio_source.clone(),
-            },
-        }
-    }
-}
-
 struct StreamlinkArgs {
     link: String,
     game: Game,
@@ -401,6 +643,8 @@ struct StreamlinkArgs {
     proxy: Option<Uri>,
     offset: Option<String>,
     quality: Option<Quality>,
+    /// A previous attempt failed and this one is on another CDN
+    failed_over: bool,
 }
 
 fn streamlink(mut args: StreamlinkArgs) -> Result<(), Error> {
@@ -478,10 +722,11 @@ fn streamlink(mut args: StreamlinkArgs) -> Result<(), Error> {
             custom_player,
         } => {
             let title = format!(
-                "{} @ {} - {} - {}",
+                "{} @ {}{} - {} - {}",
                 args.game.away_team.name,
                 args.game.home_team.name,
-                args.stream.feed_type,
+                args.game.game_number_suffix(),
+                args.stream.feed_name(),
                 args.game
                     .game_date
                     .with_timezone(&Local)
@@ -509,16 +754,25 @@ fn streamlink(mut args: StreamlinkArgs) -> Result<(), Error> {
             output,
             audio_source,
         } => {
-            let filename = format!(
-                "{} {} @ {} {}.mp4",
+            let mut filename = format!(
+                "{} {} @ {}{} {}",
                 args.game
                     .game_date
                     .with_timezone(&Local)
                     .format("%Y-%m-%d %H%M"),
                 args.game.away_team.name,
                 args.game.home_team.name,
-                args.stream.feed_type
+                args.game.game_number_suffix(),
+                args.stream.feed_name()
             );
+            // Don't overwrite what was recorded before failing over
+            if let (true, Some(cdn)) = (args.failed_over, args.stream.cdn()) {
+                filename.push_str(&format!(" ({})", cdn));
+            }
+            filename.push_str(match args.stream.kind {
+                StreamKind::Audio => ".aac",
+                _ => ".mp4",
+            });
             output.push(filename);
 
             if let Some(source) = audio_source {
@@ -559,7 +813,7 @@ fn streamlink(mut args: StreamlinkArgs) -> Result<(), Error> {
         .wait()?;
 
     if !result.success() {
-        bail!("StreamLink failed");
+        return Err(LazyStreamError::StreamlinkFailed.into());
     }
 
     match &args.command {
@@ -609,26 +863,72 @@ fn check_vlc() -> Result<(), Error> {
     Ok(())
 }
 
-/// Make sure output directory exists and can be written to
-fn check_output(directory: &PathBuf) -> Result<(), Error> {
-    if !directory.is_dir() {
-        bail!("Output diretory does not exist, please create it");
+/// Warn if the audio source isn't one of the audio renditions in the master playlist
+async fn check_audio_source(stream: &mut Stream, cdn: Cdn, audio_source: &str) {
+    if let Ok(master_playlist) = stream.master_playlist(cdn).await {
+        let mut names = vec![];
+        for media in master_playlist.media {
+            if media.media_type != "AUDIO" {
+                continue;
+            }
+
+            let language = media.language.as_deref().unwrap_or("");
+            if media.name.eq_ignore_ascii_case(audio_source)
+                || language.eq_ignore_ascii_case(audio_source)
+            {
+                return;
+            }
+
+            if !names.contains(&media.name) {
+                names.push(media.name);
+            }
+        }
+
+        if !names.is_empty() {
+            println!(
+                "Audio source {} not found in stream, available sources are: {}",
+                audio_source,
+                names.join(", ")
+            );
+        }
     }
+}
 
-    Ok(())
+/// Make sure output directory exists and can be written to
+fn check_output(directory: Option<&PathBuf>) -> Result<PathBuf, Error> {
+    match directory {
+        Some(directory) if directory.is_dir() => Ok(directory.clone()),
+        _ => Err(LazyStreamError::InvalidOutput.into()),
+    }
 }
 
 const SERVICE_NAME: &str = "_googlecast._tcp.local";
 
+/// Chromecast to cast to, the one specified or one picked from those found on the LAN
+async fn cast_host_or_search(cast_host: Option<&str>) -> Result<String, Error> {
+    if let Some(cast_host) = cast_host {
+        return Ok(cast_host.to_owned());
+    }
+
+    let cast_devices = task::spawn_blocking(|| {
+        print!("\nSearching for cast devices...");
+        let _ = std::io::stdout().flush();
+        find_cast_devices()
+    })
+    .await?;
+
+    Ok(select_cast_device(cast_devices)?.to_string())
+}
+
 #[allow(clippy::unnecessary_unwrap)]
 fn find_cast_devices() -> Result<HashMap<Ipv4Addr, String>, Error> {
     let mut devices = HashMap::new();
 
     for response in mdns::discover::all(SERVICE_NAME)
-        .map_err(|_| format_err!("mDNS discovery failed"))?
+        .map_err(|_| LazyStreamError::CastDiscovery)?
         .timeout(Duration::from_secs(2))
     {
-        let response = response.map_err(|_| format_err!("mDNS response failed"))?;
+        let response = response.map_err(|_| LazyStreamError::CastDiscovery)?;
 
         let mut ip = None;
         let mut name = None;
@@ -656,7 +956,7 @@ fn find_cast_devices() -> Result<HashMap<Ipv4Addr, String>, Error> {
 
 fn select_cast_device(devices: HashMap<Ipv4Addr, String>) -> Result<Ipv4Addr, Error> {
     if devices.is_empty() {
-        bail!("No castable devices found on LAN");
+        return Err(LazyStreamError::NoCastDevices.into());
     }
 
     println!("\rPick a cast device...        \n");
@@ -0,0 +1,235 @@
+use crate::{api::model::Team, error::LazyStreamError, league::LeagueProvider};
+use failure::Error;
+
+/// Most suggestions listed when input doesn't resolve to a single team
+const MAX_SUGGESTIONS: usize = 5;
+
+/// Resolve what was typed for a team, E.g. `vgk`, `Golden Knights` or `vegas`, to one of
+/// the league's teams. Matches are case-insensitive against the abbreviation, names,
+/// location and common nicknames of each team. Input matching more than one team, or
+/// close to a team but not matching it, fails with suggestions
+pub fn resolve<'a>(
+    teams: &'a [Team],
+    league: &dyn LeagueProvider,
+    typed: &str,
+) -> Result<&'a Team, Error> {
+    let input = normalize(typed);
+    let aliases = league.team_aliases();
+
+    let team_keys: Vec<(&Team, Vec<String>)> = teams
+        .iter()
+        .map(|team| (team, keys(team, aliases)))
+        .collect();
+
+    // Partial matches are only tried for input long enough to not match most teams
+    let exact = matching(&team_keys, |key| *key == input);
+    let matches = if exact.is_empty() && input.len() >= 3 {
+        matching(&team_keys, |key| key.contains(&input))
+    } else {
+        exact
+    };
+
+    match matches.as_slice() {
+        [team] => return Ok(*team),
+        [] => {}
+        _ => {
+            let found = suggestions(&matches);
+            return Err(LazyStreamError::AmbiguousTeam(typed.to_owned(), found).into());
+        }
+    }
+
+    // Nothing matched, suggest teams that are a typo away
+    let mut close: Vec<_> = team_keys
+        .iter()
+        .filter_map(|(team, keys)| {
+            let typos = keys.iter().map(|key| distance(key, &input)).min()?;
+            Some((typos, *team))
+        })
+        .filter(|(typos, _)| *typos <= max_typos(&input))
+        .collect();
+    close.sort_by_key(|(typos, _)| *typos);
+
+    if close.is_empty() {
+        Err(LazyStreamError::UnknownTeam(typed.to_owned()).into())
+    } else {
+        let close: Vec<_> = close.into_iter().map(|(_, team)| team).collect();
+        Err(LazyStreamError::TeamSuggestions(typed.to_owned(), suggestions(&close)).into())
+    }
+}
+
+/// Teams that have a key passing `test`
+fn matching<'a>(keys: &[(&'a Team, Vec<String>)], test: impl Fn(&String) -> bool) -> Vec<&'a Team> {
+    keys.iter()
+        .filter(|(_, keys)| keys.iter().any(&test))
+        .map(|(team, _)| *team)
+        .collect()
+}
+
+/// Everything a team can be matched by, normalized
+fn keys(team: &Team, aliases: &[(&str, &str)]) -> Vec<String> {
+    let mut keys = vec![
+        normalize(&team.abbreviation),
+        normalize(&team.name),
+        normalize(&team.team_name),
+        normalize(&team.short_name),
+    ];
+    keys.extend(team.location_name.as_deref().map(normalize));
+    keys.extend(
+        aliases
+            .iter()
+            .filter(|(_, abbreviation)| *abbreviation == team.abbreviation)
+            .map(|(alias, _)| (*alias).to_owned()),
+    );
+
+    keys.retain(|key| !key.is_empty());
+    keys
+}
+
+/// Lowercase with punctuation dropped and separators collapsed to single spaces, so
+/// `D-backs`, `d backs` and `D backs` are the same
+fn normalize(s: &str) -> String {
+    s.to_lowercase()
+        .replace(|c: char| c == '-' || c == '_' || c == '.', " ")
+        .chars()
+        .filter(|c| c.is_alphanumeric() || c.is_whitespace())
+        .collect::<String>()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Typos allowed for a suggestion, short input needs to be closer
+fn max_typos(input: &str) -> usize {
+    if input.chars().count() <= 4 {
+        1
+    } else {
+        2
+    }
+}
+
+/// Levenshtein distance between two strings
+fn distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+
+    for (i, a) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, b) in b.iter().enumerate() {
+            let substitution = previous[j] + if a == *b { 0 } else { 1 };
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+
+    previous[b.len()]
+}
+
+/// Teams listed as `VGK (Vegas Golden Knights)`
+fn suggestions(teams: &[&Team]) -> String {
+    teams
+        .iter()
+        .take(MAX_SUGGESTIONS)
+        .map(|team| format!("{} ({})", team.abbreviation, team.name))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::league::Nhl;
+
+    fn team(abbreviation: &str, location: &str, team_name: &str, short_name: &str) -> Team {
+        Team {
+            id: 0,
+            name: format!("{} {}", location, team_name),
+            link: String::new(),
+            abbreviation: abbreviation.to_owned(),
+            team_name: team_name.to_owned(),
+            location_name: Some(location.to_owned()),
+            first_year_of_play: None,
+            short_name: short_name.to_owned(),
+            active: true,
+        }
+    }
+
+    fn teams() -> Vec<Team> {
+        vec![
+            team("VGK", "Vegas", "Golden Knights", "Vegas"),
+            team("BOS", "Boston", "Bruins", "Boston"),
+            team("MTL", "Montréal", "Canadiens", "Montréal"),
+            team("NJD", "New Jersey", "Devils", "New Jersey"),
+            team("NYR", "New York", "Rangers", "NY Rangers"),
+            team("NYI", "New York", "Islanders", "NY Islanders"),
+        ]
+    }
+
+    fn resolve_abbreviation(typed: &str) -> Result<String, Error> {
+        let teams = teams();
+        resolve(&teams, &Nhl::default(), typed).map(|team| team.abbreviation.clone())
+    }
+
+    #[test]
+    fn matches_case_insensitively() {
+        assert_eq!(resolve_abbreviation("vgk").unwrap(), "VGK");
+        assert_eq!(resolve_abbreviation("Bos").unwrap(), "BOS");
+        assert_eq!(resolve_abbreviation("GOLDEN KNIGHTS").unwrap(), "VGK");
+        assert_eq!(resolve_abbreviation("boston bruins").unwrap(), "BOS");
+        assert_eq!(resolve_abbreviation("vegas").unwrap(), "VGK");
+        assert_eq!(resolve_abbreviation("  New-Jersey ").unwrap(), "NJD");
+        assert_eq!(resolve_abbreviation("rangers").unwrap(), "NYR");
+    }
+
+    #[test]
+    fn matches_substrings() {
+        assert_eq!(resolve_abbreviation("knig").unwrap(), "VGK");
+        assert_eq!(resolve_abbreviation("isl").unwrap(), "NYI");
+    }
+
+    #[test]
+    fn rejects_ambiguous_input() {
+        let e = resolve_abbreviation("new").unwrap_err();
+        match e.downcast_ref::<LazyStreamError>() {
+            Some(LazyStreamError::AmbiguousTeam(typed, found)) => {
+                assert_eq!(typed, "new");
+                assert!(found.contains("NJD (New Jersey Devils)"));
+                assert!(found.contains("NYR (New York Rangers)"));
+                assert!(found.contains("NYI (New York Islanders)"));
+                assert!(!found.contains("VGK"));
+            }
+            _ => panic!("expected an ambiguous team error, got {}", e),
+        }
+    }
+
+    #[test]
+    fn suggests_teams_for_typos() {
+        let e = resolve_abbreviation("bruinz").unwrap_err();
+        match e.downcast_ref::<LazyStreamError>() {
+            Some(LazyStreamError::TeamSuggestions(typed, found)) => {
+                assert_eq!(typed, "bruinz");
+                assert_eq!(found, "BOS (Boston Bruins)");
+            }
+            _ => panic!("expected team suggestions, got {}", e),
+        }
+
+        let e = resolve_abbreviation("penguins").unwrap_err();
+        assert!(matches!(
+            e.downcast_ref::<LazyStreamError>(),
+            Some(LazyStreamError::UnknownTeam(_))
+        ));
+    }
+
+    #[test]
+    fn matches_aliases() {
+        assert_eq!(resolve_abbreviation("habs").unwrap(), "MTL");
+        assert_eq!(resolve_abbreviation("Isles").unwrap(), "NYI");
+        assert_eq!(resolve_abbreviation("knights").unwrap(), "VGK");
+    }
+
+    #[test]
+    fn normalizes_input() {
+        assert_eq!(normalize("D-backs"), "d backs");
+        assert_eq!(normalize("  St. Louis   Blues "), "st louis blues");
+        assert_eq!(normalize("A's"), "as");
+    }
+}
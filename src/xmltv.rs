@@ -0,0 +1,417 @@
+use chrono::{DateTime, FixedOffset, NaiveDate};
+use std::fmt::Write;
+
+/// Format of programme times, E.g. `20200301190000 -0500`
+const TIME_FORMAT: &str = "%Y%m%d%H%M%S %z";
+
+/// Guide in the XMLTV format, see <https://github.com/XMLTV/xmltv/blob/master/xmltv.dtd>
+pub struct Tv {
+    pub generator_info_name: String,
+    pub source_info_name: String,
+    pub channels: Vec<Channel>,
+    pub programmes: Vec<Programme>,
+}
+
+pub struct Channel {
+    pub id: String,
+    pub display_name: String,
+    pub icon: Option<Icon>,
+}
+
+#[derive(Clone)]
+pub struct Icon {
+    pub src: String,
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+}
+
+impl Icon {
+    pub fn new(src: &str) -> Self {
+        Icon {
+            src: src.to_owned(),
+            width: None,
+            height: None,
+        }
+    }
+}
+
+/// Numbering of an episode in one of the systems XMLTV knows, E.g. `original-air-date`
+pub struct EpisodeNum {
+    pub system: String,
+    pub value: String,
+}
+
+/// Whether a programme is shown for the first time. XMLTV has no element for live
+/// programmes, they're new
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Airing {
+    New,
+    PreviouslyShown,
+}
+
+pub struct Programme {
+    pub channel: String,
+    pub start: DateTime<FixedOffset>,
+    pub stop: DateTime<FixedOffset>,
+    pub title: String,
+    pub sub_title: Option<String>,
+    pub desc: Option<String>,
+    /// Date the programme was first shown
+    pub date: Option<NaiveDate>,
+    pub categories: Vec<String>,
+    pub icons: Vec<Icon>,
+    pub episode_nums: Vec<EpisodeNum>,
+    pub airing: Option<Airing>,
+}
+
+impl Programme {
+    pub fn new(
+        channel: &str,
+        start: DateTime<FixedOffset>,
+        stop: DateTime<FixedOffset>,
+        title: &str,
+    ) -> Self {
+        Programme {
+            channel: channel.to_owned(),
+            start,
+            stop,
+            title: title.to_owned(),
+            sub_title: None,
+            desc: None,
+            date: None,
+            categories: vec![],
+            icons: vec![],
+            episode_nums: vec![],
+            airing: None,
+        }
+    }
+}
+
+impl Tv {
+    /// Serialize the guide, with text and attributes escaped
+    pub fn to_xml(&self) -> String {
+        let mut xml = String::new();
+        xml.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        xml.push_str("<!DOCTYPE tv SYSTEM \"xmltv.dtd\">\n\n");
+        xml.push_str(&start_tag(
+            "tv",
+            &[
+                ("generator-info-name", Some(&self.generator_info_name)),
+                ("source-info-name", Some(&self.source_info_name)),
+            ],
+        ));
+        xml.push('\n');
+
+        // Elements are written in the order the DTD requires them in
+        for channel in &self.channels {
+            let _ = writeln!(
+                xml,
+                "  {}",
+                start_tag("channel", &[("id", Some(&channel.id))])
+            );
+            text_element(&mut xml, "display-name", &channel.display_name);
+            if let Some(icon) = &channel.icon {
+                icon_element(&mut xml, icon);
+            }
+            xml.push_str("  </channel>\n");
+        }
+
+        for programme in &self.programmes {
+            let start = programme.start.format(TIME_FORMAT).to_string();
+            let stop = programme.stop.format(TIME_FORMAT).to_string();
+            let attributes = [
+                ("start", Some(&start)),
+                ("stop", Some(&stop)),
+                ("channel", Some(&programme.channel)),
+            ];
+            let _ = writeln!(xml, "  {}", start_tag("programme", &attributes));
+
+            text_element(&mut xml, "title", &programme.title);
+            if let Some(sub_title) = &programme.sub_title {
+                text_element(&mut xml, "sub-title", sub_title);
+            }
+            if let Some(desc) = &programme.desc {
+                text_element(&mut xml, "desc", desc);
+            }
+            if let Some(date) = programme.date {
+                let _ = writeln!(xml, "    <date>{}</date>", date.format("%Y%m%d"));
+            }
+            for category in &programme.categories {
+                text_element(&mut xml, "category", category);
+            }
+            for icon in &programme.icons {
+                icon_element(&mut xml, icon);
+            }
+            for episode_num in &programme.episode_nums {
+                let _ = writeln!(
+                    xml,
+                    "    {}{}</episode-num>",
+                    start_tag("episode-num", &[("system", Some(&episode_num.system))]),
+                    escape(&episode_num.value),
+                );
+            }
+            match programme.airing {
+                Some(Airing::PreviouslyShown) => xml.push_str("    <previously-shown />\n"),
+                Some(Airing::New) => xml.push_str("    <new />\n"),
+                None => {}
+            }
+
+            xml.push_str("  </programme>\n");
+        }
+
+        xml.push_str("</tv>\n");
+        xml
+    }
+}
+
+/// Start tag with the attributes that have a value
+fn start_tag(name: &str, attributes: &[(&str, Option<&String>)]) -> String {
+    format!("{}>", open_tag(name, attributes))
+}
+
+/// Tag left open, so it can be closed as a start tag or an empty element
+fn open_tag(name: &str, attributes: &[(&str, Option<&String>)]) -> String {
+    let mut tag = format!("<{}", name);
+    for (attribute, value) in attributes {
+        if let Some(value) = value {
+            let _ = write!(tag, " {}=\"{}\"", attribute, escape(value));
+        }
+    }
+    tag
+}
+
+/// Element of a channel or programme with English text
+fn text_element(xml: &mut String, name: &str, text: &str) {
+    let _ = writeln!(
+        xml,
+        "    <{name} lang=\"en\">{}</{name}>",
+        escape(text),
+        name = name
+    );
+}
+
+fn icon_element(xml: &mut String, icon: &Icon) {
+    let width = icon.width.map(|width| width.to_string());
+    let height = icon.height.map(|height| height.to_string());
+    let tag = open_tag(
+        "icon",
+        &[
+            ("src", Some(&icon.src)),
+            ("width", width.as_ref()),
+            ("height", height.as_ref()),
+        ],
+    );
+    let _ = writeln!(xml, "    {} />", tag);
+}
+
+/// Escape text for use in content and attribute values. Characters XML doesn't allow at
+/// all, E.g. control characters from a copy-pasted subhead, are dropped
+fn escape(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+
+    for c in s.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            '\t' | '\n' | '\r' => escaped.push(c),
+            c if c.is_control() || c == '\u{FFFE}' || c == '\u{FFFF}' => {}
+            c => escaped.push(c),
+        }
+    }
+
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use regex::Regex;
+    use std::collections::HashMap;
+
+    const DTD: &str = include_str!("xmltv.dtd");
+
+    /// Content model of an element declared in the DTD
+    struct ElementDecl {
+        /// Children of an element, written as `<name>` one after the other, have to match
+        children: Regex,
+        text: bool,
+        /// Attributes and whether they're required
+        attributes: Vec<(String, bool)>,
+    }
+
+    /// Element declarations of the DTD, enough of it to validate what's written here
+    fn parse_dtd(dtd: &str) -> HashMap<String, ElementDecl> {
+        let dtd = Regex::new(r"(?s)<!--.*?-->").unwrap().replace_all(dtd, "");
+        let element = Regex::new(r"(?s)<!ELEMENT\s+(\S+)\s+(.*?)>").unwrap();
+        let attlist = Regex::new(r"(?s)<!ATTLIST\s+(\S+)\s+(.*?)>").unwrap();
+        let attribute =
+            Regex::new(r#"([\w-]+)\s+(?:CDATA|\([^)]*\))\s+(#REQUIRED|#IMPLIED|"[^"]*")"#).unwrap();
+        let name = Regex::new(r"[A-Za-z][\w.-]*").unwrap();
+
+        let mut elements = HashMap::new();
+        for captures in element.captures_iter(&dtd) {
+            let model = captures[2].trim();
+            let (children, text) = if model == "EMPTY" {
+                (String::new(), false)
+            } else {
+                let text = model.contains("#PCDATA");
+                let model: String = model
+                    .replace("#PCDATA", "")
+                    .replace('(', "(?:")
+                    .replace(',', "")
+                    .split_whitespace()
+                    .collect();
+                (name.replace_all(&model, "(?:<$0>)").into_owned(), text)
+            };
+
+            elements.insert(
+                captures[1].to_owned(),
+                ElementDecl {
+                    children: Regex::new(&format!("^{}$", children)).unwrap(),
+                    text,
+                    attributes: vec![],
+                },
+            );
+        }
+
+        for captures in attlist.captures_iter(&dtd) {
+            let decl = elements.get_mut(&captures[1]).unwrap();
+            for attr in attribute.captures_iter(&captures[2]) {
+                decl.attributes
+                    .push((attr[1].to_owned(), &attr[2] == "#REQUIRED"));
+            }
+        }
+
+        elements
+    }
+
+    /// Check the document against the DTD, returning what's invalid about it
+    fn validate(xml: &str) -> Result<(), String> {
+        let elements = parse_dtd(DTD);
+        let document = roxmltree::Document::parse(xml).map_err(|e| e.to_string())?;
+
+        if document.root_element().tag_name().name() != "tv" {
+            return Err(String::from("Root element isn't tv"));
+        }
+
+        for node in document.descendants().filter(|node| node.is_element()) {
+            let name = node.tag_name().name();
+            let decl = elements
+                .get(name)
+                .ok_or_else(|| format!("Element {} isn't declared", name))?;
+
+            for attribute in node.attributes() {
+                if !decl.attributes.iter().any(|(a, _)| a == attribute.name()) {
+                    return Err(format!(
+                        "Attribute {} of {} isn't declared",
+                        attribute.name(),
+                        name
+                    ));
+                }
+            }
+            for (attribute, required) in &decl.attributes {
+                if *required && node.attribute(attribute.as_str()).is_none() {
+                    return Err(format!("Attribute {} of {} is required", attribute, name));
+                }
+            }
+
+            let children: String = node
+                .children()
+                .filter(|child| child.is_element())
+                .map(|child| format!("<{}>", child.tag_name().name()))
+                .collect();
+            if !decl.children.is_match(&children) {
+                return Err(format!("Children {} aren't allowed in {}", children, name));
+            }
+
+            let has_text = node
+                .children()
+                .any(|child| child.is_text() && !child.text().unwrap_or("").trim().is_empty());
+            if has_text && !decl.text {
+                return Err(format!("Text isn't allowed in {}", name));
+            }
+        }
+
+        Ok(())
+    }
+
+    fn guide(title: &str, desc: &str) -> Tv {
+        let offset = FixedOffset::west(5 * 3600);
+        let start = offset.ymd(2020, 3, 1).and_hms(19, 0, 0);
+
+        let mut programme =
+            Programme::new("1000", start, start + chrono::Duration::hours(3), title);
+        programme.sub_title = Some(String::from("HOME - SN"));
+        programme.desc = Some(desc.to_owned());
+        programme.date = Some(start.date().naive_local());
+        programme.categories = vec![String::from("Sports"), String::from("Ice Hockey")];
+        programme.icons = vec![Icon {
+            src: String::from("https://example.com/cut.jpg?w=320&h=180"),
+            width: Some(320),
+            height: Some(180),
+        }];
+        programme.episode_nums = vec![EpisodeNum {
+            system: String::from("original-air-date"),
+            value: String::from("2020-03-01 19:00:00"),
+        }];
+        programme.airing = Some(Airing::New);
+
+        Tv {
+            generator_info_name: String::from("lazystream"),
+            source_info_name: String::from("lazystream - test"),
+            channels: vec![Channel {
+                id: String::from("1000"),
+                display_name: String::from("Lazyman \"1\""),
+                icon: Some(Icon::new("https://example.com/logo.png")),
+            }],
+            programmes: vec![programme],
+        }
+    }
+
+    #[test]
+    fn escapes_markup() {
+        assert_eq!(
+            escape(r#"Tom & Jerry <"Bros'">"#),
+            "Tom &amp; Jerry &lt;&quot;Bros&apos;&quot;&gt;"
+        );
+        assert_eq!(escape("Line\nbreak\u{7}"), "Line\nbreak");
+    }
+
+    #[test]
+    fn guide_is_valid() {
+        let xml = guide("Golden Knights @ Bruins", "Watch the game.").to_xml();
+        assert_eq!(validate(&xml), Ok(()));
+        assert!(xml.contains(r#"start="20200301190000 -0500" stop="20200301220000 -0500""#));
+    }
+
+    #[test]
+    fn guide_with_markup_is_valid() {
+        let title = "Canadiens & <Bruins>";
+        let desc = "\"Habs\" look to 'snap' a skid & climb > .500";
+        let xml = guide(title, desc).to_xml();
+        assert_eq!(validate(&xml), Ok(()));
+
+        let document = roxmltree::Document::parse(&xml).unwrap();
+        let text = |name: &str| {
+            document
+                .descendants()
+                .find(|node| node.has_tag_name(name))
+                .and_then(|node| node.text())
+                .map(str::to_owned)
+        };
+        assert_eq!(text("title").as_deref(), Some(title));
+        assert_eq!(text("desc").as_deref(), Some(desc));
+    }
+
+    #[test]
+    fn invalid_guide_is_rejected() {
+        let xml = guide("Title", "Desc")
+            .to_xml()
+            .replace("<new />", "<new />\n    <title lang=\"en\">Late</title>");
+        assert!(validate(&xml).is_err());
+    }
+}